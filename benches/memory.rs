@@ -0,0 +1,117 @@
+//! Measures the memory a crawl holds per page and per edge.
+//!
+//! Run with `cargo bench --bench memory`; `MEMORY_BENCH_PAGES` sets the number
+//! of pages (1,000,000 by default). Exits with an error when a target is missed.
+
+use dashmap::DashMap;
+use ferris_the_crawler::{Edge, PageGraph, Url};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicIsize, Ordering};
+
+/// Bytes a page may take on top of its URL text.
+const PAGE_TARGET: f64 = 250.0;
+/// Bytes an edge may take between pages already stored.
+const EDGE_TARGET: f64 = 80.0;
+const LINKS_PER_PAGE: usize = 10;
+
+struct Counting;
+
+static ALLOCATED: AtomicIsize = AtomicIsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size() as isize, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size() as isize, Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(
+            new_size as isize - layout.size() as isize,
+            Ordering::Relaxed,
+        );
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocated() -> isize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+fn page(index: usize) -> Url {
+    let mut page = Url::new(url(index), url(index / 2), 1 + index % 5);
+    page.status = Some(200);
+    page.content_type = Some("text/html; charset=utf-8".to_string());
+    page
+}
+
+fn url(index: usize) -> String {
+    format!(
+        "https://www.example.com/section-{}/article-{}.html",
+        index % 100,
+        index
+    )
+}
+
+fn main() {
+    let pages = std::env::var("MEMORY_BENCH_PAGES")
+        .ok()
+        .and_then(|pages| pages.parse().ok())
+        .unwrap_or(1_000_000);
+    let url_text = (0..pages).map(|index| url(index).len()).sum::<usize>() as f64;
+
+    let graph = PageGraph::new();
+    let before = allocated();
+    for index in 0..pages {
+        graph.insert_page(page(index));
+    }
+    let after_pages = allocated();
+    for index in 0..pages {
+        for link in 1..=LINKS_PER_PAGE {
+            let target = (index * 31 + link * 7919) % pages;
+            graph.insert_edge(&Edge::new(url(index), url(target), 2));
+        }
+    }
+    let after_edges = allocated();
+
+    let per_page = ((after_pages - before) as f64 - url_text) / pages as f64;
+    let per_edge = (after_edges - after_pages) as f64 / graph.edge_count() as f64;
+    println!(
+        "{} pages, {} edges, {:.1} MB of URL text",
+        graph.page_count(),
+        graph.edge_count(),
+        url_text / 1e6
+    );
+    println!(
+        "per page: {:.1} bytes + URL text (target {})",
+        per_page, PAGE_TARGET
+    );
+    println!("per edge: {:.1} bytes (target {})", per_edge, EDGE_TARGET);
+    drop(graph);
+
+    // The same pages kept as whole records by URL hash, for comparison.
+    let before = allocated();
+    let records = DashMap::new();
+    for index in 0..pages {
+        records.insert(index as u64, page(index));
+    }
+    let per_record = ((allocated() - before) as f64 - url_text) / pages as f64;
+    println!(
+        "per page stored as a Url record: {:.1} bytes + URL text",
+        per_record
+    );
+    drop(records);
+
+    if per_page > PAGE_TARGET || per_edge > EDGE_TARGET {
+        eprintln!("Memory target missed");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,270 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Link texts that say nothing about where the link goes.
+const VAGUE_LINK_TEXTS: [&str; 10] = [
+    "click here",
+    "here",
+    "click",
+    "link",
+    "this link",
+    "more",
+    "read more",
+    "learn more",
+    "continue",
+    "this",
+];
+
+/// Input types that carry their own label or are not shown.
+const SELF_LABELLED_INPUTS: [&str; 5] = ["hidden", "submit", "button", "reset", "image"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum A11yRule {
+    /// An image without an alt attribute; decorative images should have an empty one
+    ImageWithoutAlt,
+    /// A link with no text, label or described image
+    EmptyLinkText,
+    /// A link whose text does not describe its target, like "click here"
+    VagueLinkText,
+    /// The page does not declare its language on `<html>`
+    MissingLang,
+    /// A form field without a label, aria-label or title
+    UnlabelledInput,
+    /// A heading more than one level below the previous one
+    SkippedHeadingLevel,
+    /// An id used by more than one element
+    DuplicateId,
+}
+
+/// An accessibility problem on a page, with the element it was found on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct A11yFinding {
+    pub rule: A11yRule,
+    pub detail: String,
+}
+
+impl A11yFinding {
+    fn new(rule: A11yRule, detail: String) -> Self {
+        A11yFinding { rule, detail }
+    }
+}
+
+/// Checks a page for common accessibility problems.
+pub fn lint(document: &Html) -> Vec<A11yFinding> {
+    let mut findings = Vec::new();
+
+    let lang = Selector::parse("html[lang]").unwrap();
+    if !document.select(&lang).any(|html| {
+        html.value()
+            .attr("lang")
+            .is_some_and(|lang| !lang.trim().is_empty())
+    }) {
+        findings.push(A11yFinding::new(
+            A11yRule::MissingLang,
+            "<html> has no lang attribute".to_string(),
+        ));
+    }
+
+    let images = Selector::parse("img:not([alt])").unwrap();
+    for image in document.select(&images) {
+        findings.push(A11yFinding::new(A11yRule::ImageWithoutAlt, describe(image)));
+    }
+
+    let links = Selector::parse("a[href]").unwrap();
+    for link in document.select(&links) {
+        let text = element_text(link);
+        if text.is_empty() && !has_label(link) && !has_described_image(link) {
+            findings.push(A11yFinding::new(A11yRule::EmptyLinkText, describe(link)));
+        } else if is_vague(&text) && !has_label(link) {
+            findings.push(A11yFinding::new(
+                A11yRule::VagueLinkText,
+                format!("{} \"{}\"", describe(link), text),
+            ));
+        }
+    }
+
+    let label_targets = Selector::parse("label[for]").unwrap();
+    let labelled = document
+        .select(&label_targets)
+        .filter_map(|label| label.value().attr("for"))
+        .collect::<HashSet<_>>();
+    let fields = Selector::parse("input, select, textarea").unwrap();
+    for field in document.select(&fields) {
+        let input_type = field.value().attr("type").unwrap_or("text");
+        if field.value().name() == "input"
+            && SELF_LABELLED_INPUTS.contains(&input_type.to_ascii_lowercase().as_str())
+        {
+            continue;
+        }
+        let by_id = field.value().id().is_some_and(|id| labelled.contains(id));
+        let in_label = field
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|ancestor| ancestor.value().name() == "label");
+        if !by_id && !in_label && !has_label(field) {
+            findings.push(A11yFinding::new(A11yRule::UnlabelledInput, describe(field)));
+        }
+    }
+
+    let headings = Selector::parse("h1, h2, h3, h4, h5, h6").unwrap();
+    let mut previous: Option<u8> = None;
+    for heading in document.select(&headings) {
+        let level = heading.value().name().as_bytes()[1] - b'0';
+        if let Some(previous) = previous
+            && level > previous + 1
+        {
+            findings.push(A11yFinding::new(
+                A11yRule::SkippedHeadingLevel,
+                format!(
+                    "<h{}> \"{}\" follows an <h{}>",
+                    level,
+                    element_text(heading),
+                    previous
+                ),
+            ));
+        }
+        previous = Some(level);
+    }
+
+    let ids = Selector::parse("[id]").unwrap();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for element in document.select(&ids) {
+        if let Some(id) = element.value().id() {
+            *counts.entry(id).or_insert(0) += 1;
+        }
+    }
+    for (id, count) in counts {
+        if count > 1 {
+            findings.push(A11yFinding::new(
+                A11yRule::DuplicateId,
+                format!("id=\"{}\" is used {} times", id, count),
+            ));
+        }
+    }
+
+    findings
+}
+
+fn element_text(element: ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn has_label(element: ElementRef) -> bool {
+    ["aria-label", "aria-labelledby", "title"]
+        .iter()
+        .any(|name| {
+            element
+                .value()
+                .attr(name)
+                .is_some_and(|value| !value.trim().is_empty())
+        })
+}
+
+fn has_described_image(element: ElementRef) -> bool {
+    let images = Selector::parse("img[alt]").unwrap();
+
+    element.select(&images).any(|image| {
+        image
+            .value()
+            .attr("alt")
+            .is_some_and(|alt| !alt.trim().is_empty())
+    })
+}
+
+fn is_vague(text: &str) -> bool {
+    let text = text
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+
+    VAGUE_LINK_TEXTS.contains(&text.as_str())
+}
+
+/// The opening tag of an element with the attributes that help find it.
+fn describe(element: ElementRef) -> String {
+    let element = element.value();
+    let attributes = ["id", "name", "type", "href", "src"]
+        .iter()
+        .filter_map(|name| Some(format!(" {}=\"{}\"", name, element.attr(name)?)))
+        .collect::<String>();
+
+    format!("<{}{}>", element.name(), attributes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(html: &str) -> Vec<A11yRule> {
+        lint(&Html::parse_document(html))
+            .into_iter()
+            .map(|finding| finding.rule)
+            .collect()
+    }
+
+    #[test]
+    fn accessible_page_has_no_findings() {
+        assert!(
+            rules(
+                r#"<html lang="en"><body>
+                <h1>Title</h1><h2>Section</h2><h3>Part</h3><h2>Other</h2>
+                <img src="a.png" alt=""><a href="/a"><img src="b.png" alt="Home"></a>
+                <a href="/b">Pricing</a><a href="/c" aria-label="Read the guide">Read more</a>
+                <label for="q">Search</label><input id="q">
+                <label>Name <input name="name"></label>
+                <input type="submit"><textarea title="Comment"></textarea>
+            </body></html>"#
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn finds_each_rule() {
+        let findings = lint(&Html::parse_document(
+            r#"<html><body>
+                <h1>Title</h1><h3>Skipped</h3>
+                <img src="a.png">
+                <a href="/a"></a><a href="/b">Click here!</a>
+                <input id="q" name="q"><select id="q"></select>
+            </body></html>"#,
+        ));
+
+        assert_eq!(
+            findings,
+            vec![
+                A11yFinding::new(
+                    A11yRule::MissingLang,
+                    "<html> has no lang attribute".to_string()
+                ),
+                A11yFinding::new(
+                    A11yRule::ImageWithoutAlt,
+                    r#"<img src="a.png">"#.to_string()
+                ),
+                A11yFinding::new(A11yRule::EmptyLinkText, r#"<a href="/a">"#.to_string()),
+                A11yFinding::new(
+                    A11yRule::VagueLinkText,
+                    r#"<a href="/b"> "Click here!""#.to_string()
+                ),
+                A11yFinding::new(
+                    A11yRule::UnlabelledInput,
+                    r#"<input id="q" name="q">"#.to_string()
+                ),
+                A11yFinding::new(A11yRule::UnlabelledInput, r#"<select id="q">"#.to_string()),
+                A11yFinding::new(
+                    A11yRule::SkippedHeadingLevel,
+                    r#"<h3> "Skipped" follows an <h1>"#.to_string()
+                ),
+                A11yFinding::new(
+                    A11yRule::DuplicateId,
+                    r#"id="q" is used 2 times"#.to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -1,16 +1,26 @@
+use crate::exporter::{Collapse, Column, Format};
+use crate::filter::Rule;
+use crate::frontier::{Order, Score};
+use crate::robots::RobotsPolicy;
+use crate::scope::ScopeMode;
+use crate::style::{ColorRule, EdgeColor, NodeColor, NodeSize};
+
 use clap::{Parser, Subcommand, ValueHint};
 
 #[derive(Parser, Clone, Debug)]
 #[command(version, about)]
 pub struct Args {
+    /// Only `--explain` runs without one, see [`Args::mode`].
     #[clap(subcommand)]
-    pub command: Command,
+    pub command: Option<Command>,
 
     #[clap(
         short = 'u',
         long = "url",
         help = "The URL to start crawling from",
         value_hint = ValueHint::Url,
+        required_unless_present = "explain",
+        default_value = ""
     )]
     pub url: String,
 
@@ -30,6 +40,153 @@ pub struct Args {
     )]
     pub output: Option<String>,
 
+    #[clap(
+        long = "format",
+        help = "The format of the output file",
+        value_enum,
+        default_value = "csv"
+    )]
+    pub format: Format,
+
+    #[clap(
+        long = "columns",
+        help = "The columns to write in CSV output",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "url,source,depth"
+    )]
+    pub columns: Vec<Column>,
+
+    #[clap(
+        long = "dynamic",
+        help = "Include discovery timestamps in GEXF output for Gephi's timeline"
+    )]
+    pub dynamic: bool,
+
+    #[clap(
+        long = "collapse",
+        help = "Merge nodes into paths or hosts in DOT and Mermaid output",
+        value_enum,
+        default_value = "none"
+    )]
+    pub collapse: Collapse,
+
+    #[clap(
+        long = "canonical-identity",
+        help = "Merge pages into their rel=canonical URL in exports"
+    )]
+    pub canonical_identity: bool,
+
+    #[clap(
+        long = "canonical-audit",
+        help = "Write a JSON report of canonical link problems to this file",
+        value_hint = ValueHint::FilePath
+    )]
+    pub canonical_audit: Option<String>,
+
+    #[clap(
+        long = "robots",
+        help = "How to treat robots meta tags, X-Robots-Tag headers and nofollow links",
+        value_enum,
+        default_value = "ignore"
+    )]
+    pub robots: RobotsPolicy,
+
+    #[clap(
+        long = "warc",
+        help = "Archive every request and response in WARC files named <PREFIX>-NNNNN.warc.gz"
+    )]
+    pub warc: Option<String>,
+
+    #[clap(
+        long = "warc-max-size",
+        help = "Start a new WARC file once the current one reaches this many bytes",
+        default_value = "1000000000"
+    )]
+    pub warc_max_size: u64,
+
+    #[clap(
+        long = "store",
+        help = "Keep response bodies in a content-addressed store in this directory",
+        value_hint = ValueHint::DirPath
+    )]
+    pub store: Option<String>,
+
+    #[clap(
+        long = "duplicates",
+        help = "Write a JSON report of pages with identical bodies to this file",
+        value_hint = ValueHint::FilePath
+    )]
+    pub duplicates: Option<String>,
+
+    #[clap(
+        long = "near-duplicates",
+        help = "Write a JSON report of clusters of pages with similar text to this file",
+        value_hint = ValueHint::FilePath
+    )]
+    pub near_duplicates: Option<String>,
+
+    #[clap(
+        long = "similarity",
+        help = "The share of SimHash bits two pages must share to be near-duplicates (0 to 1)",
+        default_value = "0.9"
+    )]
+    pub similarity: f64,
+
+    #[clap(
+        long = "a11y",
+        help = "Check pages for accessibility problems and write a JSON report to this file",
+        value_hint = ValueHint::FilePath
+    )]
+    pub a11y: Option<String>,
+
+    #[clap(
+        long = "security",
+        help = "Check HTTPS pages for mixed content and security headers, writing a per-host JSON summary here",
+        value_hint = ValueHint::FilePath
+    )]
+    pub security: Option<String>,
+
+    #[cfg(feature = "sqlite")]
+    #[clap(
+        long = "sqlite",
+        help = "Store results in a SQLite database, resuming the crawl it holds",
+        value_hint = ValueHint::FilePath
+    )]
+    pub sqlite: Option<String>,
+
+    #[clap(
+        long = "scope",
+        help = "Which links to follow, relative to the start URL",
+        value_enum,
+        default_value = "any"
+    )]
+    pub scope: ScopeMode,
+
+    #[clap(
+        long = "scope-path",
+        help = "The path prefix of the path scope, the directory of the start URL by default"
+    )]
+    pub scope_path: Option<String>,
+
+    #[clap(
+        long = "allow-host",
+        help = "Follow links to this host and its subdomains whatever the scope"
+    )]
+    pub allow_hosts: Vec<String>,
+
+    #[clap(
+        long = "deny-host",
+        help = "Never follow links to this host and its subdomains"
+    )]
+    pub deny_hosts: Vec<String>,
+
+    #[clap(
+        long = "record-out-of-scope",
+        help = "Keep links leaving the scope in the graph, without crawling them"
+    )]
+    pub record_out_of_scope: bool,
+
     #[clap(
         short = 'q',
         long = "ignore-query",
@@ -52,6 +209,94 @@ pub struct Args {
     )]
     pub ignore: Vec<String>,
 
+    #[clap(long = "max-pages", help = "Stop after fetching this many pages")]
+    pub max_pages: Option<usize>,
+
+    #[clap(long = "max-bytes", help = "Stop after downloading this many bytes")]
+    pub max_bytes: Option<u64>,
+
+    #[clap(
+        long = "max-duration",
+        help = "Stop after crawling for this many seconds"
+    )]
+    pub max_duration: Option<u64>,
+
+    #[clap(
+        long = "max-pages-per-host",
+        help = "Fetch at most this many pages from each host"
+    )]
+    pub max_pages_per_host: Option<usize>,
+
+    #[clap(
+        long = "rule",
+        allow_hyphen_values = true,
+        help = "Include (+ PATTERN) or exclude (- PATTERN) URLs, the first matching rule wins; patterns are substrings, re:REGEX or glob:GLOB"
+    )]
+    pub rules: Vec<Rule>,
+
+    #[clap(
+        long = "explain",
+        help = "Tell which filter rule allows or rejects this URL, without crawling",
+        value_hint = ValueHint::Url
+    )]
+    pub explain: Option<String>,
+
+    #[clap(
+        long = "accept-type",
+        help = "Also parse pages with this content type, besides text/html and application/xhtml+xml"
+    )]
+    pub accept_types: Vec<String>,
+
+    #[clap(
+        long = "max-body-size",
+        help = "Stop reading bodies larger than this many bytes",
+        default_value = "10485760"
+    )]
+    pub max_body_size: u64,
+
+    #[clap(
+        long = "head-first",
+        help = "Send a HEAD request before each page, skipping the GET if the page would not be parsed"
+    )]
+    pub head_first: bool,
+
+    #[clap(
+        long = "order",
+        help = "The order links are crawled in",
+        value_enum,
+        default_value = "bfs"
+    )]
+    pub order: Order,
+
+    #[clap(
+        long = "score",
+        help = "How links are ranked by the best-first order",
+        value_enum,
+        default_value = "in-links"
+    )]
+    pub score: Score,
+
+    #[clap(
+        long = "workers",
+        help = "Number of pages crawled at the same time",
+        default_value = "8"
+    )]
+    pub workers: usize,
+
+    #[clap(
+        long = "frontier-dir",
+        help = "Spill the links waiting to be crawled to this directory when they do not fit in memory",
+        value_hint = ValueHint::DirPath
+    )]
+    pub frontier_dir: Option<String>,
+
+    #[clap(
+        long = "frontier-memory",
+        help = "Number of waiting links kept in memory with --frontier-dir",
+        default_value = "100000"
+    )]
+    pub frontier_memory: usize,
+
     #[clap(
         short = 't',
         long = "threads",
@@ -67,6 +312,55 @@ pub struct Args {
         default_value = "http://localhost:8088/workspace1"
     )]
     pub gephi_url: String,
+
+    #[clap(
+        long = "gephi-dry-run",
+        help = "Stream to an in-process mock of Gephi and report what it received"
+    )]
+    pub gephi_dry_run: bool,
+
+    #[clap(
+        long = "gephi-color",
+        help = "How to color nodes in Gephi",
+        value_enum,
+        default_value = "host"
+    )]
+    pub gephi_color: NodeColor,
+
+    #[clap(
+        long = "gephi-size",
+        help = "How to size nodes in Gephi",
+        value_enum,
+        default_value = "in-degree"
+    )]
+    pub gephi_size: NodeSize,
+
+    #[clap(
+        long = "gephi-edge-color",
+        help = "How to color edges in Gephi",
+        value_enum,
+        default_value = "link-type"
+    )]
+    pub gephi_edge_color: EdgeColor,
+
+    #[clap(
+        long = "gephi-color-rule",
+        help = "Pin the Gephi color of a host and its subdomains (HOST=#RRGGBB)"
+    )]
+    pub gephi_color_rules: Vec<ColorRule>,
+
+    #[clap(
+        long = "serve",
+        help = "Serve a live view of the crawl graph on the given address (e.g. 127.0.0.1:8000)"
+    )]
+    pub serve: Option<String>,
+}
+
+impl Args {
+    /// The crawl mode, HTML when no subcommand was given.
+    pub fn mode(&self) -> &Command {
+        self.command.as_ref().unwrap_or(&Command::Html)
+    }
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -89,4 +383,39 @@ pub enum Command {
         )]
         link: Option<u32>,
     },
+    Mirror {
+        #[clap(
+            help = "The directory to save pages and assets in",
+            value_hint = ValueHint::DirPath
+        )]
+        dir: String,
+    },
+    Audit {
+        #[clap(
+            help = "The directory to write audit.html and audit.json in",
+            value_hint = ValueHint::DirPath
+        )]
+        dir: String,
+
+        #[clap(
+            long = "sitemap",
+            help = "The sitemap listing pages that should be linked, /sitemap.xml by default",
+            value_hint = ValueHint::Url
+        )]
+        sitemap: Option<String>,
+
+        #[clap(
+            long = "max-title-length",
+            help = "Report titles longer than this many characters",
+            default_value = "60"
+        )]
+        max_title_length: usize,
+
+        #[clap(
+            long = "thin-content",
+            help = "Report pages with fewer words than this as thin content",
+            default_value = "200"
+        )]
+        thin_content: usize,
+    },
 }
@@ -0,0 +1,203 @@
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    Pages,
+    Bytes,
+    Duration,
+    HostPages,
+}
+
+impl std::fmt::Display for BudgetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BudgetKind::Pages => "page",
+            BudgetKind::Bytes => "byte",
+            BudgetKind::Duration => "duration",
+            BudgetKind::HostPages => "per-host page",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Limits on how much a crawl fetches, shared by all its tasks.
+///
+/// Once the page, byte or duration budget runs out no new page is fetched, while
+/// requests already sent complete; the per-host budget only skips pages of that host.
+#[derive(Debug, Default)]
+pub struct Budget {
+    pub max_pages: Option<usize>,
+    pub max_bytes: Option<u64>,
+    pub max_duration: Option<Duration>,
+    pub max_host_pages: Option<usize>,
+    pages: AtomicUsize,
+    bytes: AtomicU64,
+    host_pages: DashMap<String, usize>,
+    started: OnceLock<Instant>,
+    exhausted: OnceLock<BudgetKind>,
+}
+
+impl Budget {
+    pub fn new(
+        max_pages: Option<usize>,
+        max_bytes: Option<u64>,
+        max_duration: Option<Duration>,
+        max_host_pages: Option<usize>,
+    ) -> Self {
+        Budget {
+            max_pages,
+            max_bytes,
+            max_duration,
+            max_host_pages,
+            ..Default::default()
+        }
+    }
+
+    /// Starts the clock of the duration budget.
+    pub fn start(&self) {
+        let _ = self.started.set(Instant::now());
+    }
+
+    /// The budget that stopped the crawl, if one ran out.
+    pub fn exhausted(&self) -> Option<BudgetKind> {
+        if let (Some(max), Some(started)) = (self.max_duration, self.started.get())
+            && started.elapsed() >= max
+        {
+            let _ = self.exhausted.set(BudgetKind::Duration);
+        }
+
+        self.exhausted.get().copied()
+    }
+
+    /// Takes one page out of the budget before fetching it, or tells which budget is out.
+    pub fn spend_page(&self, url: &str) -> Result<(), BudgetKind> {
+        if let Some(kind) = self.exhausted() {
+            return Err(kind);
+        }
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        // The host entry stays locked until the page is counted, so concurrent tasks cannot overshoot.
+        let mut host_pages = self.host_pages.entry(host).or_insert(0);
+        if self.max_host_pages.is_some_and(|max| *host_pages >= max) {
+            return Err(BudgetKind::HostPages);
+        }
+        if let Some(max) = self.max_pages
+            && self.pages.fetch_add(1, Ordering::SeqCst) >= max
+        {
+            let _ = self.exhausted.set(BudgetKind::Pages);
+            return Err(BudgetKind::Pages);
+        }
+        *host_pages += 1;
+
+        Ok(())
+    }
+
+    pub fn spend_bytes(&self, bytes: usize) {
+        let total = self.bytes.fetch_add(bytes as u64, Ordering::SeqCst) + bytes as u64;
+        if self.max_bytes.is_some_and(|max| total >= max) {
+            let _ = self.exhausted.set(BudgetKind::Bytes);
+        }
+    }
+
+    /// Hosts that used up their page budget.
+    pub fn exhausted_hosts(&self) -> Vec<String> {
+        let Some(max) = self.max_host_pages else {
+            return Vec::new();
+        };
+        let mut hosts = self
+            .host_pages
+            .iter()
+            .filter(|entry| *entry.value() >= max)
+            .map(|entry| entry.key().clone())
+            .collect::<Vec<_>>();
+        hosts.sort();
+
+        hosts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_budget_never_runs_out() {
+        let budget = Budget::default();
+        budget.start();
+        for _ in 0..100 {
+            assert_eq!(budget.spend_page("https://a.test/"), Ok(()));
+        }
+        budget.spend_bytes(usize::MAX / 2);
+
+        assert_eq!(budget.exhausted(), None);
+        assert!(budget.exhausted_hosts().is_empty());
+    }
+
+    #[test]
+    fn page_budget_stops_the_crawl() {
+        let budget = Budget::new(Some(2), None, None, None);
+
+        assert_eq!(budget.spend_page("https://a.test/1"), Ok(()));
+        assert_eq!(budget.spend_page("https://b.test/2"), Ok(()));
+        assert_eq!(budget.exhausted(), None);
+        assert_eq!(
+            budget.spend_page("https://a.test/3"),
+            Err(BudgetKind::Pages)
+        );
+        assert_eq!(budget.exhausted(), Some(BudgetKind::Pages));
+    }
+
+    #[test]
+    fn host_budget_only_skips_that_host() {
+        let budget = Budget::new(Some(4), None, None, Some(1));
+
+        assert_eq!(budget.spend_page("https://a.test/1"), Ok(()));
+        assert_eq!(
+            budget.spend_page("https://a.test/2"),
+            Err(BudgetKind::HostPages)
+        );
+        // Skipped pages are not taken out of the page budget.
+        assert_eq!(budget.spend_page("https://b.test/1"), Ok(()));
+        assert_eq!(budget.spend_page("https://c.test/1"), Ok(()));
+        assert_eq!(budget.spend_page("https://d.test/1"), Ok(()));
+        assert_eq!(budget.exhausted(), None);
+        assert_eq!(
+            budget.exhausted_hosts(),
+            ["a.test", "b.test", "c.test", "d.test"]
+        );
+    }
+
+    #[test]
+    fn byte_budget_runs_out_at_the_limit() {
+        let budget = Budget::new(None, Some(1000), None, None);
+
+        budget.spend_bytes(600);
+        assert_eq!(budget.exhausted(), None);
+        budget.spend_bytes(400);
+        assert_eq!(budget.exhausted(), Some(BudgetKind::Bytes));
+        assert_eq!(budget.spend_page("https://a.test/"), Err(BudgetKind::Bytes));
+    }
+
+    #[test]
+    fn duration_budget_counts_from_the_start() {
+        let budget = Budget::new(None, Some(1), Some(Duration::ZERO), None);
+        assert_eq!(budget.exhausted(), None);
+
+        budget.start();
+        assert_eq!(budget.exhausted(), Some(BudgetKind::Duration));
+        // The first budget to run out is the one reported.
+        budget.spend_bytes(1);
+        assert_eq!(
+            budget.spend_page("https://a.test/"),
+            Err(BudgetKind::Duration)
+        );
+        assert_eq!(BudgetKind::HostPages.to_string(), "per-host page");
+    }
+}
@@ -1,21 +1,49 @@
-use crate::{Args, Exporter, GephiClient, Url};
+use crate::a11y::{A11yFinding, lint};
+use crate::budget::{Budget, BudgetKind};
+use crate::filter::Filter;
+use crate::frontier::{Candidate, Frontier, InLinks, Order, Queued, Score, Scorer, UrlDepth};
+use crate::metadata::PageMetadata;
+use crate::mirror::Mirror;
+use crate::page_graph::PageGraph;
+use crate::robots::{Directives, NOFOLLOW_RELS};
+use crate::scope::Scope;
+use crate::security::{SecurityFinding, check_document, check_headers};
+use crate::sink::{Exchange, Fetch};
+use crate::store::{BodyStore, body_hash};
+use crate::text::{simhash, visible_text};
+use crate::url_data::{Skip, unix_millis};
+use crate::{
+    Args, CrawlMetadata, CrawlSink, Edge, Exporter, GephiClient, GraphEvent, Link, LiveServer,
+    MockGephi, Styler, Url,
+};
 
 use anyhow::{Context, Result};
 use dashmap::DashMap;
+use reqwest::Method;
 use scraper::{Html, Selector};
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::{HashSet, hash_map::DefaultHasher},
     hash::{Hash, Hasher},
 };
 use tokio::runtime;
 use tokio::sync::Mutex;
 use tracing::{error, info};
 
-#[derive(Clone)]
 pub struct CrawlerData {
     pub args: Args,
-    pub visited_urls: DashMap<u64, Url>,
+    pub graph: PageGraph,
+    /// Links queued to each URL so far, for best-first scoring.
+    pub queued_links: DashMap<u64, usize>,
+    pub styler: Styler,
+    pub scope: Scope,
+    pub filter: Filter,
+    pub budget: Arc<Budget>,
+    pub started_at: OnceLock<u64>,
+    pub finished_at: OnceLock<u64>,
 }
 
 #[derive(Clone)]
@@ -24,6 +52,58 @@ pub struct Crawler {
     pub tasks: Arc<Mutex<Vec<tokio::task::JoinHandle<Result<()>>>>>,
     pub send_tasks: Arc<Mutex<Vec<tokio::task::JoinHandle<Result<()>>>>>,
     pub gephi_client: Option<Arc<Mutex<GephiClient>>>,
+    pub live_server: Option<Arc<LiveServer>>,
+    pub sinks: Vec<Arc<dyn CrawlSink>>,
+    pub pending: Arc<Vec<Edge>>,
+    pub frontier: Arc<Frontier>,
+    pub scorer: Arc<dyn Scorer>,
+    pub client: reqwest::Client,
+    pub mirror: Option<Arc<Mirror>>,
+    pub store: Option<Arc<BodyStore>>,
+}
+
+pub(crate) const USER_AGENT: &str = "Mozilla/5.0 (compatible; Crawler/1.0)";
+
+/// How many redirects a request follows before failing.
+const MAX_REDIRECTS: usize = 10;
+
+/// Content types parsed as HTML, besides the ones from `--accept-type`.
+const HTML_TYPES: [&str; 2] = ["text/html", "application/xhtml+xml"];
+
+/// A response read in full.
+struct Response {
+    /// The URL that answered, after following redirects.
+    url: String,
+    status: u16,
+    content_type: Option<String>,
+    robots: Directives,
+    redirects: Vec<String>,
+    /// The status of the first redirect, if any.
+    redirect_status: Option<u16>,
+    headers: reqwest::header::HeaderMap,
+    body: Vec<u8>,
+    /// Set when the body was not read, or not read in full.
+    skipped: Option<Skip>,
+}
+
+impl Response {
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The body decoded with the charset from the Content-Type, falling back to UTF-8.
+    fn text(&self) -> String {
+        let encoding = self
+            .content_type
+            .as_deref()
+            .and_then(|content_type| content_type.split("charset=").nth(1))
+            .and_then(|charset| {
+                encoding_rs::Encoding::for_label(charset.trim_matches('"').as_bytes())
+            })
+            .unwrap_or(encoding_rs::UTF_8);
+
+        encoding.decode(&self.body).0.into_owned()
+    }
 }
 
 impl Crawler {
@@ -34,27 +114,442 @@ impl Crawler {
             Some(Arc::new(Mutex::new(GephiClient::new(&args.gephi_url))))
         };
 
+        let styler = Styler {
+            node_color: args.gephi_color,
+            node_size: args.gephi_size,
+            edge_color: args.gephi_edge_color,
+            color_rules: args.gephi_color_rules.clone(),
+            max_depth: args.depth as usize,
+        };
+
+        let scope = Scope::new(&args.url, args.scope, args.scope_path.as_deref())
+            .with_allow_hosts(&args.allow_hosts)
+            .with_deny_hosts(&args.deny_hosts);
+
+        let filter = Filter::from_args(&args.rules, &args.filters, &args.ignore);
+
+        let budget = Budget::new(
+            args.max_pages,
+            args.max_bytes,
+            args.max_duration.map(std::time::Duration::from_secs),
+            args.max_pages_per_host,
+        );
+
+        let mirror = match args.mode() {
+            crate::args::Command::Mirror { dir } => Some(Arc::new(Mirror::new(dir))),
+            _ => None,
+        };
+
+        let scorer: Arc<dyn Scorer> = match args.score {
+            Score::UrlDepth => Arc::new(UrlDepth),
+            // The sitemap scorer needs the sitemap, it is set with `with_scorer`.
+            Score::InLinks | Score::Sitemap => Arc::new(InLinks),
+        };
+        let frontier = match &args.frontier_dir {
+            Some(dir) => Frontier::new(args.order).with_spill(Path::new(dir), args.frontier_memory),
+            None => Frontier::new(args.order),
+        };
+
+        // Redirects are followed by `follow`, so that every hop reaches the sinks.
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to build HTTP client");
+
         Crawler {
             data: Arc::new(CrawlerData {
                 args,
-                visited_urls: DashMap::new(),
+                graph: PageGraph::new(),
+                queued_links: DashMap::new(),
+                styler,
+                scope,
+                filter,
+                budget: Arc::new(budget),
+                started_at: OnceLock::new(),
+                finished_at: OnceLock::new(),
             }),
             tasks: Arc::new(Mutex::new(Vec::new())),
             send_tasks: Arc::new(Mutex::new(Vec::new())),
             gephi_client,
+            live_server: None,
+            sinks: Vec::new(),
+            pending: Arc::new(Vec::new()),
+            frontier: Arc::new(frontier),
+            scorer,
+            client,
+            mirror,
+            store: None,
+        }
+    }
+
+    /// Restores pages and edges stored by a previous run.
+    ///
+    /// Pages that were never fetched, and links to pages that were never recorded,
+    /// are crawled again; everything else is kept as visited.
+    pub fn resume(mut self, pages: Vec<Url>, edges: Vec<Edge>) -> Self {
+        let mut pending = Vec::new();
+        for page in pages {
+            match page.status {
+                Some(_) => self.add_visited_url(page),
+                None => pending.push(Edge::new(page.found_at, page.url, page.depth)),
+            }
+        }
+        let mut queued: HashSet<u64> = pending
+            .iter()
+            .map(|edge| hash_string(&edge.target))
+            .collect();
+
+        for edge in edges {
+            self.data.graph.insert_edge(&edge);
+            if !self.is_visited(&edge.target) && queued.insert(hash_string(&edge.target)) {
+                pending.push(edge);
+            }
         }
+        info!(
+            "Resuming with {} visited pages and {} pending links",
+            self.data.graph.page_count(),
+            pending.len()
+        );
+        self.pending = Arc::new(pending);
+
+        self
+    }
+
+    pub fn with_store(mut self, store: Arc<BodyStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Ranks the links of a best-first crawl.
+    pub fn with_scorer(mut self, scorer: Arc<dyn Scorer>) -> Self {
+        self.scorer = scorer;
+        self
+    }
+
+    pub fn with_sink(mut self, sink: Arc<dyn CrawlSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    pub fn with_gephi_mock(mut self, mock: &MockGephi) -> Self {
+        self.gephi_client = Some(Arc::new(Mutex::new(GephiClient::new(&mock.url()))));
+        self
+    }
+
+    pub fn with_live_server(mut self, server: Arc<LiveServer>) -> Self {
+        self.live_server = Some(server);
+        self
+    }
+
+    /// Every page and edge of the crawl as it is, for the audits.
+    pub fn crawl_exporter(&self) -> Exporter {
+        Exporter::new(self.urls(), self.edges()).with_metadata(self.metadata())
+    }
+
+    /// Turns the crawl as it is into what the export options ask for.
+    pub fn export_view(&self, exporter: Exporter) -> Exporter {
+        exporter
+            .with_indexes_noindex(self.args().robots.indexes_noindex())
+            .with_columns(&self.args().columns)
+            .with_dynamic(self.args().dynamic)
+            .with_collapse(self.args().collapse)
+            .with_canonical_identity(self.args().canonical_identity)
     }
 
     pub fn exporter(&self) -> Exporter {
-        Exporter::new(self.urls())
+        self.export_view(self.crawl_exporter())
+    }
+
+    pub fn metadata(&self) -> CrawlMetadata {
+        let mode = match self.args().mode() {
+            crate::args::Command::Html => "html",
+            crate::args::Command::Wiki { .. } => "wiki",
+            crate::args::Command::Mirror { .. } => "mirror",
+            crate::args::Command::Audit { .. } => "audit",
+        };
+
+        CrawlMetadata {
+            crawler_version: env!("CARGO_PKG_VERSION").to_string(),
+            start_url: self.args().url.clone(),
+            mode: mode.to_string(),
+            max_depth: self.args().depth,
+            started_at: self.data.started_at.get().copied(),
+            finished_at: self.data.finished_at.get().copied(),
+            pages: self.data.graph.page_count(),
+            edges: self.data.graph.edge_count(),
+            stopped_by: self.data.budget.exhausted(),
+        }
     }
 
     pub fn add_visited_url(&self, url: Url) {
-        self.data.visited_urls.insert(hash_string(&url.url), url);
+        self.data.graph.insert_page(url);
+    }
+
+    /// Records a page about to be crawled, returning false if it was already visited.
+    pub fn claim_visited_url(&self, url: Url) -> bool {
+        self.data.graph.insert_new_page(url)
+    }
+
+    pub fn update_visited_url(&self, url: &str, update: impl FnOnce(&mut Url)) {
+        self.data.graph.update_page(url, update);
     }
 
     pub fn is_visited(&self, url: &str) -> bool {
-        self.data.visited_urls.contains_key(&hash_string(url))
+        self.data.graph.contains_page(url)
+    }
+
+    pub fn add_edge(&self, source: &str, target: &str, depth: usize, rel: &[String]) -> bool {
+        if depth == 0 || source == target || !self.is_graph_source(source) {
+            return false;
+        }
+
+        let mut edge = Edge::new(source.to_string(), target.to_string(), depth);
+        edge.rel = rel.to_vec();
+        if !self.data.graph.insert_edge(&edge) {
+            return false;
+        }
+
+        for sink in &self.sinks {
+            if let Err(e) = sink.edge(&edge) {
+                error!("Failed to record edge {} -> {}: {:?}", source, target, e);
+            }
+        }
+
+        true
+    }
+
+    fn record_fetch(
+        &self,
+        url: &str,
+        status: u16,
+        content_type: Option<String>,
+        bytes: usize,
+        started: Instant,
+    ) {
+        let fetch = Fetch {
+            url: url.to_string(),
+            status,
+            content_type,
+            bytes,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            fetched_at: unix_millis(),
+        };
+
+        for sink in &self.sinks {
+            if let Err(e) = sink.fetch(&fetch) {
+                error!("Failed to record fetch of {}: {:?}", url, e);
+            }
+        }
+    }
+
+    /// Whether a body is left alone, from the headers of its response.
+    ///
+    /// Bodies of other types than the parsed ones are only skipped for pages,
+    /// not for the assets of a mirror.
+    fn skip(&self, headers: &reqwest::header::HeaderMap, page: bool) -> Option<Skip> {
+        if page && !self.parses(content_type(headers).as_deref()) {
+            return Some(Skip::ContentType);
+        }
+
+        content_length(headers)
+            .is_some_and(|length| length > self.args().max_body_size)
+            .then_some(Skip::TooLarge)
+    }
+
+    /// Whether pages of a content type are parsed, which they are when it is unknown.
+    fn parses(&self, content_type: Option<&str>) -> bool {
+        let Some(content_type) = content_type else {
+            return true;
+        };
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        HTML_TYPES.contains(&media_type.as_str())
+            || self
+                .args()
+                .accept_types
+                .iter()
+                .any(|accepted| accepted.eq_ignore_ascii_case(&media_type))
+    }
+
+    /// Asks for the headers of a page, returning them if its body would be skipped.
+    async fn head(&self, url: &str) -> Option<(u16, Option<String>, Skip)> {
+        let response = self.follow(Method::HEAD, url, false).await.ok()?;
+        if !response.is_success() {
+            return None;
+        }
+        let skipped = self.skip(&response.headers, true)?;
+
+        Some((response.status, response.content_type, skipped))
+    }
+
+    /// Sends a GET request and reads the response, handing the exchange to the sinks.
+    ///
+    /// The body is streamed up to the maximum body size; a body that would be
+    /// skipped is not read, one that turns out larger is cut at that size.
+    async fn get(&self, url: &str, page: bool) -> Result<Response> {
+        self.follow(Method::GET, url, page).await
+    }
+
+    /// Sends a request and follows its redirects, handing every exchange to the sinks.
+    async fn follow(&self, method: Method, url: &str, page: bool) -> Result<Response> {
+        let start = url::Url::parse(url).context(format!("Invalid request to {}", url))?;
+        let mut target = start.clone();
+        let mut redirects: Vec<String> = Vec::new();
+        let mut redirect_status = None;
+        loop {
+            let mut response = self.send(&method, target.clone(), page).await?;
+            let location = match (300..400).contains(&response.status) {
+                true => response
+                    .headers
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .and_then(|location| target.join(location).ok()),
+                false => None,
+            };
+            let Some(mut location) = location else {
+                response.redirects = redirects;
+                response.redirect_status = redirect_status;
+                return Ok(response);
+            };
+
+            location.set_fragment(None);
+            if redirects.len() >= MAX_REDIRECTS {
+                return Err(anyhow::anyhow!("Too many redirects from {}", url));
+            }
+            if location == start || redirects.contains(&location.to_string()) {
+                return Err(anyhow::anyhow!("Redirect loop from {}", url));
+            }
+            redirect_status.get_or_insert(response.status);
+            redirects.push(location.to_string());
+            target = location;
+        }
+    }
+
+    /// Sends a single request and reads its response, without following redirects.
+    async fn send(&self, method: &Method, url: url::Url, page: bool) -> Result<Response> {
+        let request = self
+            .client
+            .request(method.clone(), url.clone())
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .header(reqwest::header::ACCEPT, "*/*")
+            .build()
+            .context(format!("Invalid request to {}", url))?;
+        let request_headers = request.headers().clone();
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .context(format!("Failed to send request to {}", url))?;
+        let final_url = response.url().to_string();
+        let remote_addr = response.remote_addr();
+        let version = response.version();
+        let status = response.status();
+        let response_headers = response.headers().clone();
+        let mut skipped = match status.is_success() {
+            true => self.skip(&response_headers, page),
+            false => None,
+        };
+        // HEAD responses announce a body they do not have.
+        if *method == Method::HEAD {
+            skipped = None;
+        }
+
+        let mut response = response;
+        let mut body = Vec::new();
+        let mut read = 0;
+        if skipped.is_none() {
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .context("Failed to read response body")?
+            {
+                read += chunk.len();
+                if read as u64 > self.args().max_body_size {
+                    let room = (self.args().max_body_size as usize).saturating_sub(body.len());
+                    body.extend_from_slice(&chunk[..room]);
+                    skipped = Some(Skip::TooLarge);
+                    break;
+                }
+                body.extend_from_slice(&chunk);
+            }
+        }
+        self.data.budget.spend_bytes(read);
+
+        let exchange = Exchange {
+            method,
+            url: &final_url,
+            remote_addr,
+            request_headers: &request_headers,
+            version,
+            status,
+            response_headers: &response_headers,
+            body: &body,
+            skipped,
+        };
+        for sink in &self.sinks {
+            if let Err(e) = sink.exchange(&exchange) {
+                error!("Failed to record exchange with {}: {:?}", final_url, e);
+            }
+        }
+
+        Ok(Response {
+            url: final_url,
+            status: status.as_u16(),
+            content_type: content_type(&response_headers),
+            robots: response_headers
+                .get_all("x-robots-tag")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .map(Directives::parse)
+                .fold(Directives::default(), Directives::merge),
+            redirects: Vec::new(),
+            redirect_status: None,
+            headers: response_headers,
+            body,
+            skipped,
+        })
+    }
+
+    /// Hashes the body of a page, keeping it in the body store if there is one.
+    fn record_body(&self, url: &str, body: &[u8]) -> Result<()> {
+        let hash = match &self.store {
+            Some(store) => store.put(body)?,
+            None => body_hash(body),
+        };
+        self.update_visited_url(url, |page| page.body_hash = Some(hash));
+
+        Ok(())
+    }
+
+    fn record_error(&self, url: &str, crawl_error: &anyhow::Error) {
+        for sink in &self.sinks {
+            if let Err(e) = sink.error(url, crawl_error) {
+                error!("Failed to record error for {}: {:?}", url, e);
+            }
+        }
+    }
+
+    /// Hands the final record of a visited page to the sinks.
+    fn finish_page(&self, url: &str) {
+        let Some(page) = self.data.graph.page(url) else {
+            return;
+        };
+
+        for sink in &self.sinks {
+            if let Err(e) = sink.page(&page) {
+                error!("Failed to record page {}: {:?}", url, e);
+            }
+        }
+    }
+
+    pub fn in_degree(&self, url: &str) -> usize {
+        self.data.graph.in_degree(url)
     }
 
     pub fn args(&self) -> &Args {
@@ -62,11 +557,21 @@ impl Crawler {
     }
 
     pub fn urls(&self) -> Vec<Url> {
-        self.data
-            .visited_urls
-            .iter()
-            .map(|entry| entry.value().clone())
-            .collect()
+        self.data.graph.pages().collect()
+    }
+
+    pub fn edges(&self) -> Vec<Edge> {
+        self.data.graph.edges().collect()
+    }
+
+    /// The visited pages, without collecting them all first.
+    pub fn iter_urls(&self) -> impl Iterator<Item = Url> + '_ {
+        self.data.graph.pages()
+    }
+
+    /// The edges, without collecting them all first.
+    pub fn iter_edges(&self) -> impl Iterator<Item = Edge> + '_ {
+        self.data.graph.edges()
     }
 
     pub async fn add_task(&self, task: tokio::task::JoinHandle<Result<()>>) {
@@ -78,40 +583,108 @@ impl Crawler {
     }
 
     pub async fn gephi_add(&self, source: &str, target: &str, depth: usize) -> Result<()> {
-        match self.args().command {
-            crate::args::Command::Wiki { amount: _, link: _ } => {
-                if source.contains("Random") {
-                    return Ok(());
-                }
+        if !self.is_graph_source(source) {
+            return Ok(());
+        }
+
+        info!(
+            "Adding edge from {} to {} at depth {}",
+            source, target, depth
+        );
+
+        // `depth` is the depth of the target, the page linking to it sits one level above.
+        let source_depth = depth.saturating_sub(1);
+        let styler = &self.data.styler;
+        self.publish(&[
+            GraphEvent::AddNode {
+                id: source.to_string(),
+                label: source.to_string(),
+                depth: source_depth,
+                style: styler.node(source, source_depth, self.in_degree(source)),
+            },
+            GraphEvent::AddNode {
+                id: target.to_string(),
+                label: target.to_string(),
+                depth,
+                style: styler.node(target, depth, self.in_degree(target)),
+            },
+            GraphEvent::AddEdge {
+                id: format!("{}-{}", source, target),
+                source: source.to_string(),
+                target: target.to_string(),
+                directed: true,
+                style: styler.edge(source, target),
+            },
+        ])
+        .await;
+
+        Ok(())
+    }
+
+    pub async fn gephi_status(&self, url: &str, status: u16) -> Result<()> {
+        let Some(style) = self.data.styler.status(status) else {
+            return Ok(());
+        };
+
+        self.publish(&[GraphEvent::ChangeNode {
+            id: url.to_string(),
+            style,
+        }])
+        .await;
+
+        Ok(())
+    }
+
+    async fn publish(&self, events: &[GraphEvent]) {
+        if let Some(server) = &self.live_server {
+            for event in events {
+                server.publish(event);
             }
-            _ => {}
         }
 
         if let Some(client) = &self.gephi_client {
-            info!(
-                "Adding edge from {} to {} at depth {}",
-                source, target, depth
-            );
-
             let client = client.lock().await;
-            match client.add_node(source, source, depth).await {
-                Ok(_) => info!("Node {} added successfully", source),
-                Err(e) => error!("Failed to add node {}: {:?}", source, e),
-            };
-            match client.add_node(target, target, depth + 1).await {
-                Ok(_) => info!("Node {} added successfully", target),
-                Err(e) => error!("Failed to add node {}: {:?}", target, e),
-            };
-            match client
-                .add_edge(&format!("{}-{}", source, target), source, target, true)
-                .await
-            {
-                Ok(_) => info!("Edge {} -> {} added successfully", source, target),
-                Err(e) => error!("Failed to add edge {} -> {}: {:?}", source, target, e),
-            };
+            for event in events {
+                match client.send(event).await {
+                    Ok(_) => info!("Sent to Gephi: {:?}", event),
+                    Err(e) => error!("Failed to send {:?} to Gephi: {:?}", event, e),
+                };
+            }
+        }
+    }
+
+    /// Accessibility findings for a page, when the check is enabled.
+    fn a11y(&self, document: &Html) -> Vec<A11yFinding> {
+        match self.args().a11y {
+            Some(_) => lint(document),
+            None => Vec::new(),
         }
+    }
 
-        Ok(())
+    /// Mixed content and missing security headers of an HTTPS page, when the check is enabled.
+    fn security(&self, document: &Html, response: &Response) -> Option<Vec<SecurityFinding>> {
+        match &self.args().security {
+            Some(_) if response.url.starts_with("https:") => {
+                let mut findings = check_headers(&response.headers);
+                findings.extend(check_document(document, &response.url));
+                Some(findings)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a link is crawled under the robots policy, given the page it was found on.
+    fn follows(&self, from: &str, link: &Link) -> bool {
+        if self.args().robots.follows_nofollow() {
+            return true;
+        }
+        let from_nofollow = self.data.graph.is_nofollow(from);
+
+        !link.is_nofollow() && !from_nofollow
+    }
+
+    fn is_graph_source(&self, source: &str) -> bool {
+        !matches!(self.args().mode(), crate::args::Command::Wiki { .. } if source.contains("Random"))
     }
 
     pub fn crawl(&self) -> Result<()> {
@@ -121,51 +694,71 @@ impl Crawler {
             .build()
             .context("Failed to create Tokio runtime")?;
 
+        let _ = self.data.started_at.set(unix_time());
+        self.data.budget.start();
         rt.block_on(async {
-            match self.args().command {
-                crate::args::Command::Html => {
-                    self.add_task(self.spawn_crawl(
-                        self.args().url.to_string(),
+            match *self.args().mode() {
+                crate::args::Command::Html
+                | crate::args::Command::Mirror { .. }
+                | crate::args::Command::Audit { .. } => {
+                    self.enqueue(
+                        Link::new(self.args().url.to_string()),
                         self.args().url.to_string(),
                         0,
-                    ))
+                    )
                     .await;
                 }
                 crate::args::Command::Wiki { amount, link: _ } => {
                     for _ in 0..amount {
-                        self.add_task(self.spawn_crawl(
-                            self.args().url.to_string(),
+                        self.enqueue(
+                            Link::new(self.args().url.to_string()),
                             self.args().url.to_string(),
                             0,
-                        ))
+                        )
                         .await;
                     }
                 }
             }
 
+            for edge in self.pending.iter() {
+                self.enqueue(
+                    Link::new(edge.target.clone()),
+                    edge.source.clone(),
+                    edge.depth,
+                )
+                .await;
+            }
+
+            for _ in 0..self.args().workers.max(1) {
+                self.add_task(self.spawn_worker()).await;
+            }
+
             self.wait_for_tasks().await?;
 
-            Ok(())
-        })
-    }
+            Ok::<_, anyhow::Error>(())
+        })?;
+        if let Some(mirror) = &self.mirror {
+            mirror.rewrite_links()?;
+        }
+        let _ = self.data.finished_at.set(unix_time());
 
-    async fn crawl_html_url(&self, url: String, from: String, depth: usize) -> Result<()> {
-        if depth > self.args().depth as usize {
-            return Ok(());
+        let metadata = self.metadata();
+        for sink in &self.sinks {
+            sink.finish(&metadata)?;
         }
 
-        let mut url = url;
+        Ok(())
+    }
+
+    /// The URL a link is crawled as, `None` if it is not a valid URL.
+    fn normalize_url(&self, link: &str) -> Option<String> {
+        let mut url = link.to_string();
         if url.starts_with("/") {
             let base_url = self.args().url.clone();
             let full_url = format!("{}{}", base_url, url);
             url = full_url;
         }
-        let url_struct: url::Url = match url.parse() {
-            Ok(parsed_url) => parsed_url,
-            Err(_) => {
-                return Ok(());
-            }
-        };
+        let url_struct: url::Url = url.parse().ok()?;
         let url = match self.args().ignore_query {
             false => url_struct.to_string(),
             true => {
@@ -175,79 +768,178 @@ impl Crawler {
             }
         };
 
-        if self.args().filters.len() > 0 {
-            let mut allowed = false;
-            for filter in &self.args().filters {
-                if url.contains(filter) {
-                    allowed = true;
-                    break;
-                }
-            }
-            if !allowed {
-                info!("Skipping: {} (not in filters)", url);
-                return Ok(());
-            }
+        Some(url)
+    }
+
+    /// Tells which filter rule decides on a URL, as the crawler would see it.
+    pub fn explain(&self, url: &str) -> String {
+        match self.normalize_url(url) {
+            Some(url) => self.data.filter.explain(&url),
+            None => format!("skipped {}, it is not a valid URL", url),
         }
-        if self.args().ignore.len() > 0 {
-            let mut ignored = false;
-            for ignore in &self.args().ignore {
-                if url.contains(ignore) {
-                    ignored = true;
-                    break;
-                }
-            }
-            if ignored {
-                info!("Skipping: {} (in ignore list)", url);
+    }
+
+    async fn crawl_html_url(&self, link: Link, from: String, depth: usize) -> Result<()> {
+        if depth > self.args().depth as usize {
+            return Ok(());
+        }
+
+        let Some(url) = self.normalize_url(&link.url) else {
+            return Ok(());
+        };
+
+        if !self.data.filter.allows(&url) {
+            info!("Skipping: {} (filtered out)", url);
+            return Ok(());
+        }
+
+        // The start URL is crawled even if the scope options leave it out.
+        let in_scope = depth == 0 || self.data.scope.contains(&url);
+        if !in_scope && !self.args().record_out_of_scope {
+            info!("Skipping: {} (out of scope)", url);
+            return Ok(());
+        }
+
+        // Pages are claimed and taken out of the budget before their edge is
+        // recorded, so the pages a budget leaves out do not show up in the graph.
+        let follows = in_scope && self.follows(&from, &link);
+        let claimed = follows && self.claim_visited_url(Url::new(url.clone(), from.clone(), depth));
+        let mut fetch = claimed;
+        if claimed
+            && depth < self.args().depth as usize
+            && let Err(budget) = self.data.budget.spend_page(&url)
+        {
+            info!("Not fetching: {} ({} budget exhausted)", url, budget);
+            // Pages over the per-host budget stay in the graph, like pages past the maximum depth.
+            if budget != BudgetKind::HostPages {
+                self.data.graph.remove_page(&url);
                 return Ok(());
             }
+            fetch = false;
         }
 
+        self.add_edge(&from, &url, depth, &link.rel);
         self.add_send_task(self.spawn_add_gephi(from.clone(), url.clone(), depth))
             .await;
 
-        if self.is_visited(&url) {
+        if !in_scope {
+            info!("Not following: {} (out of scope)", url);
+            return Ok(());
+        }
+        if !follows {
+            info!("Not following: {} (nofollow)", url);
+            return Ok(());
+        }
+        if !claimed {
             info!("Already visited: {}", url);
             return Ok(());
         }
 
-        self.add_visited_url(Url {
-            url: url.clone(),
-            found_at: from.clone(),
-            depth,
-        });
+        let result = match fetch {
+            true => self.fetch_html_page(&url, depth).await,
+            false => Ok(()),
+        };
+        if let Err(e) = &result {
+            self.record_error(&url, e);
+        }
+        self.finish_page(&url);
+
+        result
+    }
 
+    async fn fetch_html_page(&self, url: &str, depth: usize) -> Result<()> {
         if depth + 1 > self.args().depth as usize {
             info!("Max depth: {}", url);
             return Ok(());
         }
 
         info!("Crawling (depth: {}): {}", depth, url);
-        let client = reqwest::Client::new();
-        let response = client
-            .get(&url)
-            .header("User-Agent", "Mozilla/5.0 (compatible; Crawler/1.0)")
-            .send()
-            .await
-            .context(format!("Failed to send request to {}", &url))?;
-        if !response.status().is_success() {
+        let started = Instant::now();
+        if self.args().head_first
+            && let Some((status, content_type, skipped)) = self.head(url).await
+        {
+            self.update_visited_url(url, |page| {
+                page.status = Some(status);
+                page.content_type = content_type.clone();
+                page.skipped = Some(skipped);
+            });
+            self.add_send_task(self.spawn_gephi_status(url.to_string(), status))
+                .await;
+            self.record_fetch(url, status, content_type, 0, started);
+            info!("Skipping: {} ({})", url, skipped);
+            return Ok(());
+        }
+
+        let response = self.get(url, true).await?;
+        let status = response.status;
+        self.update_visited_url(url, |page| {
+            page.status = Some(status);
+            page.content_type = response.content_type.clone();
+            page.redirects = response.redirects.clone();
+            page.redirect_status = response.redirect_status;
+            page.skipped = response.skipped;
+        });
+        self.add_send_task(self.spawn_gephi_status(url.to_string(), status))
+            .await;
+        self.record_fetch(
+            url,
+            status,
+            response.content_type.clone(),
+            response.body.len(),
+            started,
+        );
+        if !response.is_success() {
             return Err(anyhow::anyhow!(
                 "Request to {} failed with status: {}",
                 url,
-                response.status()
+                status
             ));
         }
+        if let Some(skipped) = response.skipped {
+            info!("Skipping: {} ({})", url, skipped);
+            return Ok(());
+        }
         info!("Fetched: {}", url);
+        self.record_body(url, &response.body)?;
+        if let Some(mirror) = &self.mirror
+            && let Err(e) = mirror.save(url, response.content_type.as_deref(), &response.body)
+        {
+            error!("Failed to mirror {}: {:?}", url, e);
+        }
 
-        let html = response
-            .text()
-            .await
-            .context("Failed to read response text")?;
-        let links = self.extract_links(&html);
+        let html = response.text();
+        let (links, assets, metadata, simhash, a11y, security) = {
+            let document = Html::parse_document(&html);
+            let assets = match self.mirror {
+                Some(_) => extract_assets(&document, url),
+                None => Vec::new(),
+            };
+            (
+                self.extract_links(&document, url),
+                assets,
+                PageMetadata::extract(&document, url),
+                simhash(&visible_text(&document)),
+                self.a11y(&document),
+                self.security(&document, &response),
+            )
+        };
+        let directives = page_directives(&metadata, response.robots);
+        self.update_visited_url(url, |page| {
+            page.metadata = metadata;
+            page.simhash = simhash;
+            page.a11y = a11y;
+            page.security_checked = security.is_some();
+            page.security = security.unwrap_or_default();
+            page.noindex = directives.noindex;
+            page.nofollow = directives.nofollow;
+        });
         info!("Found {} links on {} at depth {}", links.len(), url, depth);
 
         for link in links {
-            self.add_task(self.spawn_crawl(link, url.clone(), depth + 1))
-                .await;
+            self.enqueue(link, url.to_string(), depth + 1).await;
+        }
+        for asset in assets {
+            self.add_task(self.spawn_mirror_asset(asset)).await;
         }
 
         Ok(())
@@ -256,13 +948,20 @@ impl Crawler {
     pub async fn wait_for_tasks(&self) -> Result<()> {
         let self_clone1 = self.clone();
         let self_clone2 = self.clone();
+        let crawl_done = Arc::new(AtomicBool::new(false));
+        let send_loop_done = crawl_done.clone();
 
         let send_task_loop = tokio::spawn(async move {
             loop {
+                // Read the flag before taking the tasks so nothing queued before it was set is lost.
+                let done = send_loop_done.load(Ordering::SeqCst);
                 let send_tasks: Vec<_> = {
                     let mut locked = self_clone1.send_tasks.lock().await;
                     std::mem::take(&mut *locked)
                 };
+                if send_tasks.is_empty() && done {
+                    break;
+                }
 
                 if !send_tasks.is_empty() {
                     let results = futures::future::join_all(send_tasks).await;
@@ -291,25 +990,33 @@ impl Crawler {
                 let results = futures::future::join_all(tasks).await;
 
                 for result in results {
-                    if let Err(e) = result {
-                        error!("Task failed: {:?}", e);
+                    match result {
+                        Ok(Err(e)) => error!("Task failed: {:?}", e),
+                        Err(e) => error!("Task panicked: {:?}", e),
+                        Ok(Ok(())) => {}
                     }
                 }
             }
         });
 
-        // Wait for regular tasks to finish; send_task_loop is infinite, so we detach it.
-        let (_, regular_result) = futures::join!(send_task_loop, regular_task_loop);
+        // Wait for regular tasks to finish, then let the send loop flush what is left.
+        let regular_result = regular_task_loop.await;
+        crawl_done.store(true, Ordering::SeqCst);
+        send_task_loop
+            .await
+            .map_err(|e| anyhow::anyhow!("Send task join error: {:?}", e))?;
         regular_result.map_err(|e| anyhow::anyhow!("Task join error: {:?}", e))?;
 
         Ok(())
     }
 
-    async fn crawl_url(&self, url: String, from: String, depth: usize) -> Result<()> {
-        match self.args().command {
-            crate::args::Command::Html => self.crawl_html_url(url, from, depth).await,
+    async fn crawl_url(&self, link: Link, from: String, depth: usize) -> Result<()> {
+        match self.args().mode() {
+            crate::args::Command::Html
+            | crate::args::Command::Mirror { .. }
+            | crate::args::Command::Audit { .. } => self.crawl_html_url(link, from, depth).await,
             crate::args::Command::Wiki { amount: _, link: _ } => {
-                self.crawl_wiki_url(url, from, depth).await
+                self.crawl_wiki_url(link.url, from, depth).await
             }
         }
     }
@@ -319,6 +1026,7 @@ impl Crawler {
             return Ok(());
         }
 
+        self.add_edge(&from, &url, depth, &[]);
         self.add_send_task(self.spawn_add_gephi(from.clone(), url.clone(), depth))
             .await;
 
@@ -339,35 +1047,70 @@ impl Crawler {
                 return Ok(());
             }
 
-            self.add_visited_url(Url {
-                url: url.clone(),
-                found_at: from.clone(),
-                depth,
-            });
+            if !self.claim_visited_url(Url::new(url.clone(), from.clone(), depth)) {
+                info!("Already visited: {}", url);
+                return Ok(());
+            }
+        }
+
+        let result = self.fetch_wiki_page(&url, depth).await;
+        if let Err(e) = &result {
+            self.record_error(&url, e);
         }
+        self.finish_page(&url);
 
+        result
+    }
+
+    async fn fetch_wiki_page(&self, url: &str, depth: usize) -> Result<()> {
         if depth + 1 > self.args().depth as usize {
             info!("Max depth: {}", url);
             return Ok(());
         }
+        if let Err(budget) = self.data.budget.spend_page(url) {
+            info!("Not fetching: {} ({} budget exhausted)", url, budget);
+            if budget != BudgetKind::HostPages {
+                self.data.graph.remove_page(url);
+            }
+            return Ok(());
+        }
 
         info!("Crawling (depth: {}): {}", depth, url);
-        let reqsponse = reqwest::get(&url)
-            .await
-            .context(format!("Failed to send request to {}", &url))?;
-        let html = reqsponse
-            .text()
-            .await
-            .context("Failed to read response text")?;
+        let started = Instant::now();
+        let response = self.get(url, true).await?;
+        let status = response.status;
+        self.update_visited_url(url, |page| {
+            page.status = Some(status);
+            page.content_type = response.content_type.clone();
+            page.redirects = response.redirects.clone();
+            page.redirect_status = response.redirect_status;
+            page.skipped = response.skipped;
+        });
+        self.add_send_task(self.spawn_gephi_status(url.to_string(), status))
+            .await;
+        self.record_fetch(
+            url,
+            status,
+            response.content_type.clone(),
+            response.body.len(),
+            started,
+        );
+        if let Some(skipped) = response.skipped {
+            info!("Skipping: {} ({})", url, skipped);
+            return Ok(());
+        }
+        self.record_body(url, &response.body)?;
+        let html = response.text();
         info!("Fetched: {}", url);
 
-        let links = {
+        let (links, metadata, simhash, a11y, security) = {
             let doc = Html::parse_document(&html);
             let selector =
                 Selector::parse("#mw-content-text .mw-parser-output p a[href^=\"/wiki/\"]")
                     .unwrap();
 
-            doc.select(&selector)
+            let links = doc
+                .select(&selector)
                 .filter_map(|element| {
                     let href = element.value().attr("href")?;
                     if href.contains(":") || href.contains("#") {
@@ -379,44 +1122,103 @@ impl Crawler {
                         .ok()?;
                     Some(full_url.to_string())
                 })
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+
+            (
+                links,
+                PageMetadata::extract(&doc, url),
+                simhash(&visible_text(&doc)),
+                self.a11y(&doc),
+                self.security(&doc, &response),
+            )
         };
+        let directives = page_directives(&metadata, response.robots);
+        self.update_visited_url(url, |page| {
+            page.metadata = metadata;
+            page.simhash = simhash;
+            page.a11y = a11y;
+            page.security_checked = security.is_some();
+            page.security = security.unwrap_or_default();
+            page.noindex = directives.noindex;
+            page.nofollow = directives.nofollow;
+        });
 
-        match self.args().command {
-            crate::args::Command::Wiki { amount: _, link } => {
-                if link.is_none() {
+        if let crate::args::Command::Wiki { amount: _, link } = *self.args().mode() {
+            match link {
+                None => {
                     for link in links {
-                        self.add_task(self.spawn_crawl(link, url.clone(), depth + 1))
+                        self.enqueue(Link::new(link), url.to_string(), depth + 1)
                             .await;
                     }
-                } else {
-                    if links.len() as u32 > link.unwrap() {
-                        self.add_task(self.spawn_crawl(
-                            links[link.unwrap() as usize].clone(),
-                            url.clone(),
-                            depth + 1,
-                        ))
-                        .await;
-                    } else {
-                        info!("No link found on {}", url);
-                        return Ok(());
-                    }
+                }
+                Some(link) if (link as usize) < links.len() => {
+                    self.enqueue(
+                        Link::new(links[link as usize].clone()),
+                        url.to_string(),
+                        depth + 1,
+                    )
+                    .await;
+                }
+                Some(_) => {
+                    info!("No link found on {}", url);
+                    return Ok(());
                 }
             }
-            _ => {}
         }
 
         Ok(())
     }
 
-    fn spawn_crawl(
-        &self,
-        url: String,
-        from: String,
-        depth: usize,
-    ) -> tokio::task::JoinHandle<Result<()>> {
+    /// Queues a link in the frontier, scoring it when the crawl is best-first.
+    async fn enqueue(&self, link: Link, from: String, depth: usize) {
+        let score = match self.args().order {
+            Order::BestFirst => {
+                let in_links = {
+                    let mut count = self
+                        .data
+                        .queued_links
+                        .entry(hash_string(&link.url))
+                        .or_insert(0);
+                    *count += 1;
+                    *count
+                };
+                self.scorer.score(&Candidate {
+                    url: &link.url,
+                    from: &from,
+                    depth,
+                    in_links,
+                })
+            }
+            Order::Bfs | Order::Dfs => 0.0,
+        };
+
+        self.frontier
+            .push(Queued { link, from, depth }, score)
+            .await;
+    }
+
+    /// Crawls links from the frontier until it runs dry.
+    fn spawn_worker(&self) -> tokio::task::JoinHandle<Result<()>> {
         let crawler = self.clone();
-        tokio::spawn(async move { crawler.crawl_url(url, from, depth).await })
+        tokio::spawn(async move {
+            while let Some(Queued { link, from, depth }) = crawler.frontier.pop().await {
+                // Once a budget runs out, the links left are drained without being crawled.
+                if crawler.data.budget.exhausted().is_some() {
+                    crawler.frontier.done().await;
+                    continue;
+                }
+                // A task of its own, so a panic cannot leave the link in flight forever.
+                let page = crawler.clone();
+                match tokio::spawn(async move { page.crawl_url(link, from, depth).await }).await {
+                    Ok(Err(e)) => error!("Task failed: {:?}", e),
+                    Err(e) => error!("Task panicked: {:?}", e),
+                    Ok(Ok(())) => {}
+                }
+                crawler.frontier.done().await;
+            }
+
+            Ok(())
+        })
     }
 
     fn spawn_add_gephi(
@@ -429,20 +1231,274 @@ impl Crawler {
         tokio::spawn(async move { crawler.gephi_add(&source, &target, depth).await })
     }
 
-    fn extract_links(&self, html: &str) -> Vec<String> {
-        let document = Html::parse_document(html);
+    fn spawn_gephi_status(&self, url: String, status: u16) -> tokio::task::JoinHandle<Result<()>> {
+        let crawler = self.clone();
+        tokio::spawn(async move { crawler.gephi_status(&url, status).await })
+    }
+
+    async fn mirror_asset(&self, url: &str) -> Result<()> {
+        let Some(mirror) = &self.mirror else {
+            return Ok(());
+        };
+        if self.is_visited(url) || !mirror.claim(url) || self.data.budget.exhausted().is_some() {
+            return Ok(());
+        }
+        if !self.data.filter.allows(url) {
+            info!("Skipping asset: {} (filtered out)", url);
+            return Ok(());
+        }
+        if !self.data.scope.contains(url) {
+            info!("Skipping asset: {} (out of scope)", url);
+            return Ok(());
+        }
+
+        info!("Fetching asset: {}", url);
+        let response = self.get(url, false).await?;
+        if !response.is_success() {
+            return Err(anyhow::anyhow!(
+                "Request to {} failed with status: {}",
+                url,
+                response.status
+            ));
+        }
+        if let Some(skipped) = response.skipped {
+            info!("Skipping asset: {} ({})", url, skipped);
+            return Ok(());
+        }
+
+        mirror.save(url, response.content_type.as_deref(), &response.body)
+    }
+
+    fn spawn_mirror_asset(&self, url: String) -> tokio::task::JoinHandle<Result<()>> {
+        let crawler = self.clone();
+        tokio::spawn(async move { crawler.mirror_asset(&url).await })
+    }
+
+    fn extract_links(&self, document: &Html, page_url: &str) -> Vec<Link> {
         let selector = Selector::parse("a").unwrap();
 
         document
             .select(&selector)
-            .filter_map(|element| element.value().attr("href").map(String::from))
+            .filter_map(|element| {
+                let url = resolve_link(document, page_url, element.value().attr("href")?)?;
+                let rel = element
+                    .value()
+                    .attr("rel")
+                    .unwrap_or_default()
+                    .split_ascii_whitespace()
+                    .map(str::to_ascii_lowercase)
+                    .filter(|rel| NOFOLLOW_RELS.contains(&rel.as_str()))
+                    .collect();
+
+                Some(Link { url, rel })
+            })
             .collect()
     }
 }
 
+/// Images, scripts, stylesheets and icons a page needs to display offline.
+fn extract_assets(document: &Html, page_url: &str) -> Vec<String> {
+    let selector = Selector::parse(
+        "img[src], script[src], source[src], video[src], audio[src], link[rel~=stylesheet][href], link[rel~=icon][href]",
+    )
+    .unwrap();
+
+    document
+        .select(&selector)
+        .filter_map(|element| {
+            let element = element.value();
+            element.attr("src").or_else(|| element.attr("href"))
+        })
+        .filter_map(|src| resolve_link(document, page_url, src))
+        .collect()
+}
+
+/// The robots directives of a page, from its meta tag and its X-Robots-Tag headers.
+fn page_directives(metadata: &PageMetadata, headers: Directives) -> Directives {
+    metadata
+        .robots
+        .as_deref()
+        .map(Directives::parse)
+        .unwrap_or_default()
+        .merge(headers)
+}
+
+/// Resolves a link against the page it is on, or its `<base>`, dropping the fragment.
+pub(crate) fn resolve_link(document: &Html, page_url: &str, href: &str) -> Option<String> {
+    let base_selector = Selector::parse("base[href]").unwrap();
+    let mut base = url::Url::parse(page_url).ok()?;
+    if let Some(base_href) = document
+        .select(&base_selector)
+        .next()
+        .and_then(|element| element.value().attr("href"))
+    {
+        base = base.join(base_href).ok()?;
+    }
+
+    let mut url = base.join(href.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    url.set_fragment(None);
+
+    Some(url.to_string())
+}
+
+fn content_type(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+fn content_length(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
 fn hash_string(s: &str) -> u64 {
     let mut hasher = DefaultHasher::new();
     s.hash(&mut hasher);
 
     hasher.finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, HeaderMap, HeaderValue};
+
+    fn crawler(flags: &[&str]) -> Crawler {
+        let mut args = vec!["crawler", "-u", "https://a.test/", "-g", ""];
+        args.extend(flags);
+        args.push("html");
+
+        Crawler::new(Args::parse_from(args))
+    }
+
+    fn headers(content_type: Option<&'static str>, length: Option<&'static str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(content_type) = content_type {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+        if let Some(length) = length {
+            headers.insert(CONTENT_LENGTH, HeaderValue::from_static(length));
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_html_and_accepted_types() {
+        let crawler = crawler(&["--accept-type", "Text/Plain"]);
+
+        assert!(crawler.parses(None));
+        assert!(crawler.parses(Some("text/html")));
+        assert!(crawler.parses(Some("TEXT/HTML; charset=utf-8")));
+        assert!(crawler.parses(Some("application/xhtml+xml")));
+        assert!(crawler.parses(Some("text/plain")));
+        assert!(!crawler.parses(Some("application/pdf")));
+        assert!(!self::crawler(&[]).parses(Some("text/plain")));
+    }
+
+    #[test]
+    fn skips_bodies_from_their_headers() {
+        let crawler = crawler(&["--max-body-size", "1000"]);
+
+        assert_eq!(
+            crawler.skip(&headers(Some("text/html"), Some("1000")), true),
+            None
+        );
+        assert_eq!(crawler.skip(&headers(None, None), true), None);
+        assert_eq!(
+            crawler.skip(&headers(Some("text/html"), Some("1001")), true),
+            Some(Skip::TooLarge)
+        );
+        assert_eq!(
+            crawler.skip(&headers(Some("image/png"), Some("10")), true),
+            Some(Skip::ContentType)
+        );
+        // Mirrored assets are only held to the size cap.
+        assert_eq!(
+            crawler.skip(&headers(Some("image/png"), Some("10")), false),
+            None
+        );
+        assert_eq!(
+            crawler.skip(&headers(Some("image/png"), Some("1001")), false),
+            Some(Skip::TooLarge)
+        );
+    }
+
+    #[test]
+    fn reads_the_content_length() {
+        assert_eq!(content_length(&headers(None, Some(" 42 "))), Some(42));
+        assert_eq!(content_length(&headers(None, Some("many"))), None);
+        assert_eq!(content_length(&headers(None, None)), None);
+    }
+
+    #[test]
+    fn pages_past_the_budget_are_left_out() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = stream.unwrap();
+                let request = crate::http::read_request(&stream).unwrap();
+                let body = format!(r#"<a href="{0}a">A</a><a href="{0}b">B</a>"#, request.path);
+                crate::http::write_response(&stream, "200 OK", "text/html", body.as_bytes())
+                    .unwrap();
+            }
+        });
+        let crawler = Crawler::new(Args::parse_from([
+            "crawler",
+            "-u",
+            &base,
+            "-g",
+            "",
+            "-d",
+            "3",
+            "--max-pages",
+            "1",
+            "html",
+        ]));
+
+        crawler.crawl().unwrap();
+
+        let urls = crawler.urls();
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].status, Some(200));
+        assert!(crawler.edges().is_empty());
+    }
+
+    #[test]
+    fn explains_urls_as_they_are_crawled() {
+        let crawler = Crawler::new(Args::parse_from([
+            "crawler",
+            "--rule",
+            "- ?ref=",
+            "--ignore-query",
+            "--explain",
+            "HTTPS://A.test/page?ref=x",
+        ]));
+
+        assert!(crawler.args().command.is_none());
+        assert!(matches!(crawler.args().mode(), crate::args::Command::Html));
+        assert_eq!(
+            crawler.explain("HTTPS://A.test/page?ref=x"),
+            "allowed https://a.test/page, no rule matched"
+        );
+        assert_eq!(
+            crawler.explain("page"),
+            "skipped page, it is not a valid URL"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use super::Exporter;
+use crate::a11y::{A11yFinding, A11yRule};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Accessibility findings of a whole crawl.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct A11yReport {
+    /// HTML pages that were checked.
+    pub pages_checked: usize,
+    pub rules: Vec<A11yRuleSummary>,
+    /// Pages with findings, those with the most first.
+    pub pages: Vec<A11yPage>,
+}
+
+/// How often a rule was broken across the site.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct A11yRuleSummary {
+    pub rule: A11yRule,
+    pub findings: usize,
+    pub pages: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct A11yPage {
+    pub url: String,
+    pub findings: Vec<A11yFinding>,
+}
+
+impl Exporter {
+    pub fn a11y_report(&self) -> A11yReport {
+        let mut rules: BTreeMap<A11yRule, (usize, HashSet<&str>)> = BTreeMap::new();
+        let mut pages = Vec::new();
+        for url in &self.urls {
+            for finding in &url.a11y {
+                let (findings, pages) = rules.entry(finding.rule).or_default();
+                *findings += 1;
+                pages.insert(&url.url);
+            }
+            if !url.a11y.is_empty() {
+                pages.push(A11yPage {
+                    url: url.url.clone(),
+                    findings: url.a11y.clone(),
+                });
+            }
+        }
+        pages.sort_by(|a, b| {
+            (std::cmp::Reverse(a.findings.len()), &a.url)
+                .cmp(&(std::cmp::Reverse(b.findings.len()), &b.url))
+        });
+
+        A11yReport {
+            // Only HTML pages get a word count, so it tells which pages were parsed.
+            pages_checked: self
+                .urls
+                .iter()
+                .filter(|url| url.metadata.word_count.is_some())
+                .count(),
+            rules: rules
+                .into_iter()
+                .map(|(rule, (findings, pages))| A11yRuleSummary {
+                    rule,
+                    findings,
+                    pages: pages.len(),
+                })
+                .collect(),
+            pages,
+        }
+    }
+
+    pub fn a11y_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.a11y_report())
+            .context("Failed to serialize accessibility report")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Url;
+
+    fn page(url: &str, word_count: Option<usize>, rules: &[A11yRule]) -> Url {
+        let mut page = Url::new(url.to_string(), url.to_string(), 1);
+        page.metadata.word_count = word_count;
+        page.a11y = rules
+            .iter()
+            .map(|rule| A11yFinding {
+                rule: *rule,
+                detail: String::new(),
+            })
+            .collect();
+        page
+    }
+
+    #[test]
+    fn summarizes_rules_and_pages() {
+        let report = Exporter::new(
+            vec![
+                page("https://a.test/c", Some(10), &[A11yRule::MissingLang]),
+                page(
+                    "https://a.test/b",
+                    Some(10),
+                    &[A11yRule::ImageWithoutAlt, A11yRule::ImageWithoutAlt],
+                ),
+                page("https://a.test/", Some(10), &[]),
+                page(
+                    "https://a.test/a",
+                    Some(10),
+                    &[A11yRule::ImageWithoutAlt, A11yRule::MissingLang],
+                ),
+                page("https://a.test/logo.png", None, &[]),
+            ],
+            Vec::new(),
+        )
+        .a11y_report();
+
+        assert_eq!(report.pages_checked, 4);
+        assert_eq!(
+            report.rules,
+            [
+                A11yRuleSummary {
+                    rule: A11yRule::ImageWithoutAlt,
+                    findings: 3,
+                    pages: 2,
+                },
+                A11yRuleSummary {
+                    rule: A11yRule::MissingLang,
+                    findings: 2,
+                    pages: 2,
+                },
+            ]
+        );
+        assert_eq!(
+            report
+                .pages
+                .iter()
+                .map(|page| page.url.as_str())
+                .collect::<Vec<_>>(),
+            ["https://a.test/a", "https://a.test/b", "https://a.test/c"]
+        );
+        assert_eq!(report.pages[2].findings[0].rule, A11yRule::MissingLang);
+    }
+
+    #[test]
+    fn reports_nothing_without_findings() {
+        let report =
+            Exporter::new(vec![page("https://a.test/", Some(0), &[])], Vec::new()).a11y_report();
+
+        assert_eq!(report.pages_checked, 1);
+        assert!(report.rules.is_empty());
+        assert!(report.pages.is_empty());
+    }
+}
@@ -0,0 +1,516 @@
+use super::Exporter;
+use super::xml::escape;
+use crate::Url;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Notice,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditCheck {
+    MissingTitle,
+    DuplicateTitle,
+    LongTitle,
+    MissingDescription,
+    DuplicateDescription,
+    MissingH1,
+    MultipleH1,
+    NonCanonicalLink,
+    RedirectChain,
+    OrphanSitemapPage,
+    ThinContent,
+}
+
+impl AuditCheck {
+    pub fn severity(&self) -> Severity {
+        match self {
+            AuditCheck::MissingTitle => Severity::Error,
+            AuditCheck::DuplicateTitle
+            | AuditCheck::MissingDescription
+            | AuditCheck::MissingH1
+            | AuditCheck::NonCanonicalLink
+            | AuditCheck::RedirectChain => Severity::Warning,
+            AuditCheck::LongTitle
+            | AuditCheck::DuplicateDescription
+            | AuditCheck::MultipleH1
+            // Pages deeper than the crawl went are orphans too, so this is only a hint.
+            | AuditCheck::OrphanSitemapPage
+            | AuditCheck::ThinContent => Severity::Notice,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            AuditCheck::MissingTitle => "Missing title",
+            AuditCheck::DuplicateTitle => "Duplicate title",
+            AuditCheck::LongTitle => "Long title",
+            AuditCheck::MissingDescription => "Missing meta description",
+            AuditCheck::DuplicateDescription => "Duplicate meta description",
+            AuditCheck::MissingH1 => "Missing h1",
+            AuditCheck::MultipleH1 => "Multiple h1",
+            AuditCheck::NonCanonicalLink => "Link to a non-canonical URL",
+            AuditCheck::RedirectChain => "Redirect chain",
+            AuditCheck::OrphanSitemapPage => "Orphan sitemap page",
+            AuditCheck::ThinContent => "Thin content",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuditIssue {
+    pub url: String,
+    pub check: AuditCheck,
+    pub severity: Severity,
+    pub detail: String,
+}
+
+/// Thresholds and inputs of the checks that need more than the crawl results.
+#[derive(Debug, Clone, Default)]
+pub struct AuditSettings {
+    pub max_title_length: usize,
+    pub thin_content: usize,
+    /// URLs listed in the sitemap, expected to be linked from crawled pages.
+    pub sitemap: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AuditReport<'a> {
+    start_url: &'a str,
+    pages: usize,
+    summary: BTreeMap<Severity, usize>,
+    issues: &'a [AuditIssue],
+}
+
+impl Exporter {
+    /// Runs the SEO checks over the crawled pages, most severe issues first.
+    ///
+    /// Page checks only cover HTML pages that were fetched successfully without
+    /// redirects and do not ask to stay out of the index.
+    pub fn audit(&self, settings: &AuditSettings) -> Vec<AuditIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |url: &str, check: AuditCheck, detail: String| {
+            issues.push(AuditIssue {
+                url: url.to_string(),
+                check,
+                severity: check.severity(),
+                detail,
+            })
+        };
+
+        let pages = self
+            .urls
+            .iter()
+            .filter(|page| is_indexable_html(page))
+            .collect::<Vec<_>>();
+        let titles = group_by(&pages, |page| page.metadata.title.as_deref());
+        let descriptions = group_by(&pages, |page| page.metadata.description.as_deref());
+
+        for page in &pages {
+            let url = page.url.as_str();
+            let metadata = &page.metadata;
+
+            match metadata.title.as_deref() {
+                None => issue(url, AuditCheck::MissingTitle, "no <title>".to_string()),
+                Some(title) => {
+                    if let Some(others) = duplicates(&titles, title, url) {
+                        issue(url, AuditCheck::DuplicateTitle, others);
+                    }
+                    let length = title.chars().count();
+                    if length > settings.max_title_length {
+                        issue(
+                            url,
+                            AuditCheck::LongTitle,
+                            format!("{} characters, over {}", length, settings.max_title_length),
+                        );
+                    }
+                }
+            }
+
+            match metadata.description.as_deref() {
+                None => issue(
+                    url,
+                    AuditCheck::MissingDescription,
+                    "no <meta name=\"description\">".to_string(),
+                ),
+                Some(description) => {
+                    if let Some(others) = duplicates(&descriptions, description, url) {
+                        issue(url, AuditCheck::DuplicateDescription, others);
+                    }
+                }
+            }
+
+            match metadata.h1.len() {
+                0 => issue(url, AuditCheck::MissingH1, "no <h1>".to_string()),
+                1 => {}
+                count => issue(
+                    url,
+                    AuditCheck::MultipleH1,
+                    format!("{} <h1> elements", count),
+                ),
+            }
+
+            if let Some(words) = metadata.word_count
+                && words < settings.thin_content
+            {
+                issue(
+                    url,
+                    AuditCheck::ThinContent,
+                    format!("{} words, under {}", words, settings.thin_content),
+                );
+            }
+        }
+
+        for page in &self.urls {
+            if page.redirects.len() > 1 {
+                issue(
+                    &page.url,
+                    AuditCheck::RedirectChain,
+                    format!(
+                        "{} redirects: {} -> {}",
+                        page.redirects.len(),
+                        page.url,
+                        page.redirects.join(" -> ")
+                    ),
+                );
+            }
+        }
+
+        let by_url = self.pages();
+        let mut seen = HashSet::new();
+        for edge in &self.edges {
+            let Some(target) = by_url.get(edge.target.as_str()) else {
+                continue;
+            };
+            if host(&edge.source) != host(&edge.target)
+                || !seen.insert((&edge.source, &edge.target))
+            {
+                continue;
+            }
+            if let Some(destination) = target.redirects.last() {
+                issue(
+                    &edge.source,
+                    AuditCheck::NonCanonicalLink,
+                    format!(
+                        "links to {}, which redirects to {}",
+                        target.url, destination
+                    ),
+                );
+            } else if let Some(canonical) = target.metadata.canonical.as_deref()
+                && canonical != target.url
+            {
+                issue(
+                    &edge.source,
+                    AuditCheck::NonCanonicalLink,
+                    format!("links to {}, whose canonical is {}", target.url, canonical),
+                );
+            }
+        }
+
+        let linked = self
+            .edges
+            .iter()
+            .map(|edge| edge.target.as_str())
+            .chain(std::iter::once(self.metadata.start_url.as_str()))
+            .filter_map(normalize)
+            .collect::<HashSet<_>>();
+        let mut orphan = format!(
+            "listed in the sitemap but not linked from any page crawled within depth {}",
+            self.metadata.max_depth
+        );
+        if let Some(budget) = self.metadata.stopped_by {
+            let _ = write!(orphan, ", the crawl stopped early on its {} budget", budget);
+        }
+        for url in &settings.sitemap {
+            if normalize(url).is_some_and(|url| !linked.contains(&url)) {
+                issue(url, AuditCheck::OrphanSitemapPage, orphan.clone());
+            }
+        }
+
+        issues.sort_by(|a, b| {
+            (a.severity, a.check, &a.url, &a.detail).cmp(&(b.severity, b.check, &b.url, &b.detail))
+        });
+        issues
+    }
+
+    pub fn audit_json(&self, settings: &AuditSettings) -> Result<String> {
+        let issues = self.audit(settings);
+        let report = AuditReport {
+            start_url: &self.metadata.start_url,
+            pages: self.urls.len(),
+            summary: summary(&issues),
+            issues: &issues,
+        };
+
+        serde_json::to_string_pretty(&report).context("Failed to serialize audit report")
+    }
+
+    /// A standalone HTML page with a summary table and the issues grouped by check.
+    pub fn audit_html(&self, settings: &AuditSettings) -> String {
+        let issues = self.audit(settings);
+        let mut html = String::new();
+
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        let _ = writeln!(
+            html,
+            "<title>SEO audit of {}</title>",
+            escape(&self.metadata.start_url)
+        );
+        html.push_str(
+            "<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 2em; }\n\
+             th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }\n\
+             .error { color: #b00020; }\n\
+             .warning { color: #b36b00; }\n\
+             .notice { color: #1a5fb4; }\n\
+             </style>\n</head>\n<body>\n",
+        );
+        let _ = writeln!(
+            html,
+            "<h1>SEO audit of {}</h1>\n<p>{} pages, {} issues</p>",
+            escape(&self.metadata.start_url),
+            self.urls.len(),
+            issues.len()
+        );
+
+        let mut checks: BTreeMap<AuditCheck, Vec<&AuditIssue>> = BTreeMap::new();
+        for issue in &issues {
+            checks.entry(issue.check).or_default().push(issue);
+        }
+        let mut checks = checks.into_iter().collect::<Vec<_>>();
+        checks.sort_by_key(|(check, _)| (check.severity(), *check));
+
+        html.push_str("<table>\n<tr><th>Severity</th><th>Check</th><th>Issues</th></tr>\n");
+        for (check, issues) in &checks {
+            let _ = writeln!(
+                html,
+                "<tr><td class=\"{0}\">{0}</td><td><a href=\"#{1}\">{2}</a></td><td>{3}</td></tr>",
+                severity_name(check.severity()),
+                check_id(*check),
+                check.label(),
+                issues.len()
+            );
+        }
+        html.push_str("</table>\n");
+
+        for (check, issues) in &checks {
+            let _ = writeln!(
+                html,
+                "<h2 id=\"{}\" class=\"{}\">{}</h2>\n<table>\n<tr><th>URL</th><th>Detail</th></tr>",
+                check_id(*check),
+                severity_name(check.severity()),
+                check.label()
+            );
+            for issue in issues {
+                // Sitemap URLs come from outside the crawl, only web links are made clickable.
+                let url = match url::Url::parse(&issue.url) {
+                    Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {
+                        format!("<a href=\"{0}\">{0}</a>", escape(&issue.url))
+                    }
+                    _ => escape(&issue.url),
+                };
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    url,
+                    escape(&issue.detail)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+
+        html
+    }
+}
+
+fn is_indexable_html(page: &Url) -> bool {
+    page.status
+        .is_some_and(|status| (200..300).contains(&status))
+        && page
+            .content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.contains("html"))
+        && page.redirects.is_empty()
+        && !page.noindex
+        && page.skipped.is_none()
+}
+
+fn group_by<'a>(
+    pages: &[&'a Url],
+    key: impl Fn(&'a Url) -> Option<&'a str>,
+) -> HashMap<&'a str, Vec<&'a str>> {
+    let mut groups: HashMap<&str, Vec<&str>> = HashMap::new();
+    for page in pages {
+        if let Some(key) = key(page) {
+            groups.entry(key).or_default().push(&page.url);
+        }
+    }
+
+    groups
+}
+
+/// The other pages sharing a value with a page, if there are any.
+fn duplicates(groups: &HashMap<&str, Vec<&str>>, value: &str, url: &str) -> Option<String> {
+    let mut others = groups
+        .get(value)?
+        .iter()
+        .filter(|other| **other != url)
+        .copied()
+        .collect::<Vec<_>>();
+    if others.is_empty() {
+        return None;
+    }
+    others.sort();
+
+    Some(format!("also used by {}", others.join(", ")))
+}
+
+fn summary(issues: &[AuditIssue]) -> BTreeMap<Severity, usize> {
+    let mut summary = BTreeMap::new();
+    for issue in issues {
+        *summary.entry(issue.severity).or_insert(0) += 1;
+    }
+
+    summary
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Notice => "notice",
+    }
+}
+
+fn check_id(check: AuditCheck) -> String {
+    format!("{:?}", check)
+}
+
+fn host(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host_str().map(str::to_string)
+}
+
+/// Sitemaps and links may spell the same URL differently, compare them once parsed.
+fn normalize(url: &str) -> Option<String> {
+    url::Url::parse(url.trim()).ok().map(|url| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BudgetKind, CrawlMetadata, Edge};
+
+    fn page(url: &str, title: Option<&str>) -> Url {
+        let mut page = Url::new(url.to_string(), url.to_string(), 1);
+        page.status = Some(200);
+        page.content_type = Some("text/html".to_string());
+        page.metadata.title = title.map(String::from);
+        page.metadata.description = Some(format!("About {}", url));
+        page.metadata.h1 = vec!["Heading".to_string()];
+        page
+    }
+
+    fn exporter(urls: Vec<Url>, edges: Vec<Edge>) -> Exporter {
+        Exporter::new(urls, edges).with_metadata(CrawlMetadata {
+            start_url: "https://a.test/".to_string(),
+            max_depth: 2,
+            ..Default::default()
+        })
+    }
+
+    fn checks(issues: &[AuditIssue]) -> Vec<(&str, AuditCheck)> {
+        issues
+            .iter()
+            .map(|issue| (issue.url.as_str(), issue.check))
+            .collect()
+    }
+
+    #[test]
+    fn finds_title_problems_on_indexable_pages() {
+        let mut hidden = page("https://a.test/hidden", None);
+        hidden.noindex = true;
+        let exporter = exporter(
+            vec![
+                page("https://a.test/", Some("Home")),
+                page("https://a.test/a", Some("Home")),
+                page("https://a.test/b", None),
+                hidden,
+            ],
+            Vec::new(),
+        );
+
+        let issues = exporter.audit(&AuditSettings {
+            max_title_length: 60,
+            ..Default::default()
+        });
+        assert_eq!(
+            checks(&issues),
+            vec![
+                ("https://a.test/b", AuditCheck::MissingTitle),
+                ("https://a.test/", AuditCheck::DuplicateTitle),
+                ("https://a.test/a", AuditCheck::DuplicateTitle),
+            ]
+        );
+        assert_eq!(issues[1].detail, "also used by https://a.test/a");
+    }
+
+    #[test]
+    fn orphan_sitemap_pages_are_notices_that_mention_the_depth() {
+        let mut exporter = exporter(
+            vec![page("https://a.test/", Some("Home"))],
+            vec![Edge::new(
+                "https://a.test/".to_string(),
+                "https://a.test/linked".to_string(),
+                1,
+            )],
+        );
+        exporter.metadata.stopped_by = Some(BudgetKind::Pages);
+
+        let issues = exporter.audit(&AuditSettings {
+            max_title_length: 60,
+            sitemap: vec![
+                "https://a.test/linked".to_string(),
+                "https://a.test/orphan".to_string(),
+            ],
+            ..Default::default()
+        });
+        assert_eq!(
+            checks(&issues),
+            vec![("https://a.test/orphan", AuditCheck::OrphanSitemapPage)]
+        );
+        assert_eq!(issues[0].severity, Severity::Notice);
+        assert_eq!(
+            issues[0].detail,
+            "listed in the sitemap but not linked from any page crawled within depth 2, \
+             the crawl stopped early on its page budget"
+        );
+    }
+
+    #[test]
+    fn html_only_links_web_urls() {
+        let exporter = exporter(vec![page("https://a.test/", Some("Home"))], Vec::new());
+
+        let html = exporter.audit_html(&AuditSettings {
+            max_title_length: 60,
+            sitemap: vec![
+                "https://a.test/orphan".to_string(),
+                "javascript:alert(1)".to_string(),
+            ],
+            ..Default::default()
+        });
+        assert!(html.contains("<a href=\"https://a.test/orphan\">"));
+        assert!(html.contains("<td>javascript:alert(1)</td>"));
+        assert!(!html.contains("href=\"javascript:"));
+    }
+}
@@ -0,0 +1,327 @@
+use super::Exporter;
+use crate::scope::registrable_domain;
+use crate::{Edge, Url};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// How many canonical links are followed before giving up on a chain.
+const MAX_CANONICAL_HOPS: usize = 10;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CanonicalProblem {
+    /// The canonical URL answered with something else than 200, or redirected
+    NonOk,
+    /// The canonical URL was not crawled, so it could not be checked
+    Unverified,
+    /// The canonical URL declares another canonical URL itself
+    Chain,
+    /// The canonical URL is on another registrable domain
+    CrossDomain,
+    /// The canonical URL is the page itself, written differently
+    SelfMismatch,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalIssue {
+    pub url: String,
+    pub canonical: String,
+    pub problem: CanonicalProblem,
+    pub detail: String,
+}
+
+impl Exporter {
+    /// Merges every page into its canonical URL, so duplicates collapse into one node.
+    ///
+    /// Edges are redirected to the canonical nodes, dropping the self-loops and
+    /// duplicates this creates. Pages whose canonical was never crawled take its URL.
+    pub fn with_canonical_identity(mut self, enabled: bool) -> Self {
+        if !enabled {
+            return self;
+        }
+
+        let identities = {
+            let pages = self.pages();
+            self.urls
+                .iter()
+                .map(|url| (url.url.clone(), canonical_of(&pages, &url.url)))
+                .filter(|(url, canonical)| url != canonical)
+                .collect::<HashMap<_, _>>()
+        };
+        let identity = |url: &str| identities.get(url).cloned().unwrap_or(url.to_string());
+
+        let mut seen = HashSet::new();
+        let mut urls = Vec::with_capacity(self.urls.len());
+        // Canonical pages first, so they win over the duplicates pointing at them.
+        let (canonical, duplicates): (Vec<_>, Vec<_>) = std::mem::take(&mut self.urls)
+            .into_iter()
+            .partition(|url| !identities.contains_key(&url.url));
+        for mut url in canonical.into_iter().chain(duplicates) {
+            url.url = identity(&url.url);
+            url.found_at = identity(&url.found_at);
+            if seen.insert(url.url.clone()) {
+                urls.push(url);
+            }
+        }
+        self.urls = urls;
+
+        let mut seen = HashSet::new();
+        self.edges = std::mem::take(&mut self.edges)
+            .into_iter()
+            .filter_map(|edge| {
+                let edge = Edge {
+                    source: identity(&edge.source),
+                    target: identity(&edge.target),
+                    ..edge
+                };
+                let key = (edge.source.clone(), edge.target.clone());
+                (edge.source != edge.target && seen.insert(key)).then_some(edge)
+            })
+            .collect();
+
+        self
+    }
+
+    pub(super) fn pages(&self) -> HashMap<&str, &Url> {
+        self.urls
+            .iter()
+            .map(|page| (page.url.as_str(), page))
+            .collect()
+    }
+
+    /// Canonical links that point at errors or redirects, chain, leave the domain or
+    /// misspell the page itself, and those that could not be checked.
+    pub fn canonical_audit(&self) -> Vec<CanonicalIssue> {
+        let pages = self.pages();
+
+        let mut issues = Vec::new();
+        for page in &self.urls {
+            let Some(canonical) = page.metadata.canonical.as_deref() else {
+                continue;
+            };
+            if canonical == page.url {
+                continue;
+            }
+            let mut issue = |problem, detail: String| {
+                issues.push(CanonicalIssue {
+                    url: page.url.clone(),
+                    canonical: canonical.to_string(),
+                    problem,
+                    detail,
+                })
+            };
+
+            let target = pages.get(canonical);
+            match target.and_then(|target| Some((target, target.status?))) {
+                None => issue(
+                    CanonicalProblem::Unverified,
+                    "canonical was not crawled".to_string(),
+                ),
+                Some((target, _)) if !target.redirects.is_empty() => issue(
+                    CanonicalProblem::NonOk,
+                    format!(
+                        "canonical answered with {}, redirecting to {}",
+                        target
+                            .redirect_status
+                            .map_or("a redirect".to_string(), |status| status.to_string()),
+                        target.redirects.last().unwrap()
+                    ),
+                ),
+                Some((_, status)) if status != 200 => issue(
+                    CanonicalProblem::NonOk,
+                    format!("canonical answered with {}", status),
+                ),
+                Some(_) => {}
+            }
+            if let Some(next) = target.and_then(|target| target.metadata.canonical.as_deref())
+                && next != canonical
+            {
+                issue(
+                    CanonicalProblem::Chain,
+                    format!("canonical declares {} as canonical", next),
+                );
+            }
+            if domain(&page.url) != domain(canonical) {
+                issue(
+                    CanonicalProblem::CrossDomain,
+                    format!("canonical is on {}", domain(canonical).unwrap_or_default()),
+                );
+            }
+            if loose_form(&page.url).is_some() && loose_form(&page.url) == loose_form(canonical) {
+                issue(
+                    CanonicalProblem::SelfMismatch,
+                    "canonical differs from the page only in scheme, case or trailing slash"
+                        .to_string(),
+                );
+            }
+        }
+
+        issues
+    }
+
+    pub fn canonical_audit_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.canonical_audit())
+            .context("Failed to serialize canonical audit")
+    }
+}
+
+/// The end of the canonical chain starting at a page.
+fn canonical_of(pages: &HashMap<&str, &Url>, url: &str) -> String {
+    let mut current = url;
+    let mut visited = HashSet::from([url]);
+    for _ in 0..MAX_CANONICAL_HOPS {
+        let Some(canonical) = pages
+            .get(current)
+            .and_then(|page| page.metadata.canonical.as_deref())
+        else {
+            break;
+        };
+        if !visited.insert(canonical) {
+            break;
+        }
+        current = canonical;
+    }
+
+    current.to_string()
+}
+
+/// The registrable domain of a URL, like `example.co.uk` for `https://www.example.co.uk/`.
+fn domain(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+
+    Some(registrable_domain(parsed.host_str()?).to_string())
+}
+
+/// A URL without its scheme, path case and trailing slash, which differ between
+/// spellings of the same page. The host, port and query are kept as they are.
+fn loose_form(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let mut form = parsed.host_str()?.to_string();
+    if let Some(port) = parsed.port() {
+        form.push_str(&format!(":{}", port));
+    }
+    form.push_str(&parsed.path().trim_end_matches('/').to_ascii_lowercase());
+    if let Some(query) = parsed.query() {
+        form.push('?');
+        form.push_str(query);
+    }
+
+    Some(form)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, status: Option<u16>, canonical: Option<&str>) -> Url {
+        let mut page = Url::new(url.to_string(), url.to_string(), 1);
+        page.status = status;
+        page.metadata.canonical = canonical.map(String::from);
+        page
+    }
+
+    fn problems(pages: Vec<Url>) -> Vec<(String, CanonicalProblem)> {
+        Exporter::new(pages, Vec::new())
+            .canonical_audit()
+            .into_iter()
+            .map(|issue| (issue.url, issue.problem))
+            .collect()
+    }
+
+    #[test]
+    fn loose_form_ignores_scheme_case_and_trailing_slash() {
+        assert_eq!(
+            loose_form("http://a.test/Docs/"),
+            loose_form("https://a.test/docs")
+        );
+        assert_ne!(
+            loose_form("https://a.test/a?utm=x"),
+            loose_form("https://a.test/a")
+        );
+        assert_ne!(
+            loose_form("https://www.a.test/a"),
+            loose_form("https://a.test/a")
+        );
+        assert_ne!(
+            loose_form("https://a.test:8080/a"),
+            loose_form("https://a.test/a")
+        );
+    }
+
+    #[test]
+    fn queries_are_not_self_mismatches() {
+        let problems = problems(vec![
+            page(
+                "https://a.test/a?utm=x",
+                Some(200),
+                Some("https://a.test/a"),
+            ),
+            page("https://a.test/b/", Some(200), Some("https://a.test/B")),
+            page("https://a.test/a", Some(200), None),
+            page("https://a.test/B", Some(200), None),
+        ]);
+
+        assert_eq!(
+            problems,
+            vec![(
+                "https://a.test/b/".to_string(),
+                CanonicalProblem::SelfMismatch
+            )]
+        );
+    }
+
+    #[test]
+    fn cross_domain_uses_the_registrable_domain() {
+        let problems = problems(vec![
+            page(
+                "https://www.a.co.uk/",
+                Some(200),
+                Some("https://shop.a.co.uk/"),
+            ),
+            page("https://b.co.uk/", Some(200), Some("https://c.co.uk/")),
+            page("https://shop.a.co.uk/", Some(200), None),
+            page("https://c.co.uk/", Some(200), None),
+        ]);
+
+        assert_eq!(
+            problems,
+            vec![(
+                "https://b.co.uk/".to_string(),
+                CanonicalProblem::CrossDomain
+            )]
+        );
+    }
+
+    #[test]
+    fn redirects_and_uncrawled_canonicals_are_reported() {
+        let mut moved = page("https://a.test/moved", Some(200), None);
+        moved.redirects = vec!["https://a.test/new".to_string()];
+        moved.redirect_status = Some(301);
+        let issues = Exporter::new(
+            vec![
+                page("https://a.test/1", Some(200), Some("https://a.test/moved")),
+                page(
+                    "https://a.test/2",
+                    Some(200),
+                    Some("https://a.test/missing"),
+                ),
+                page("https://a.test/3", Some(200), Some("https://a.test/deep")),
+                page("https://a.test/deep", None, None),
+                moved,
+            ],
+            Vec::new(),
+        )
+        .canonical_audit();
+
+        assert_eq!(issues.len(), 3);
+        assert_eq!(issues[0].problem, CanonicalProblem::NonOk);
+        assert_eq!(
+            issues[0].detail,
+            "canonical answered with 301, redirecting to https://a.test/new"
+        );
+        assert_eq!(issues[1].problem, CanonicalProblem::Unverified);
+        assert_eq!(issues[2].problem, CanonicalProblem::Unverified);
+    }
+}
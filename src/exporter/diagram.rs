@@ -0,0 +1,288 @@
+use super::Exporter;
+
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Collapse {
+    /// One node per URL
+    #[default]
+    None,
+    /// One node per URL path, ignoring query strings and fragments
+    Path,
+    /// One node per host
+    Host,
+}
+
+struct DiagramNode {
+    label: String,
+    host: String,
+    depth: usize,
+}
+
+/// The crawl graph reduced to what diagrams show: collapsed nodes and weighted edges.
+struct Diagram {
+    nodes: Vec<DiagramNode>,
+    edges: BTreeMap<(usize, usize), usize>,
+}
+
+impl Diagram {
+    fn new(exporter: &Exporter) -> Self {
+        let mut diagram = Diagram {
+            nodes: Vec::new(),
+            edges: BTreeMap::new(),
+        };
+        let mut ids = HashMap::new();
+
+        let mut add_node = |diagram: &mut Diagram, url: &str, depth: usize| -> usize {
+            let (key, label, host) = collapse(url, exporter.collapse);
+            let id = *ids.entry(key).or_insert_with(|| {
+                diagram.nodes.push(DiagramNode { label, host, depth });
+                diagram.nodes.len() - 1
+            });
+            let node = &mut diagram.nodes[id];
+            node.depth = node.depth.min(depth);
+
+            id
+        };
+
+        for url in &exporter.urls {
+            add_node(&mut diagram, &url.url, url.depth);
+        }
+        for edge in &exporter.edges {
+            let source = add_node(&mut diagram, &edge.source, edge.depth.saturating_sub(1));
+            let target = add_node(&mut diagram, &edge.target, edge.depth);
+            if source != target {
+                *diagram.edges.entry((source, target)).or_insert(0) += 1;
+            }
+        }
+
+        diagram
+    }
+
+    /// Node ids grouped by depth.
+    fn ranks(&self) -> BTreeMap<usize, Vec<usize>> {
+        let mut ranks: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (id, node) in self.nodes.iter().enumerate() {
+            ranks.entry(node.depth).or_default().push(id);
+        }
+
+        ranks
+    }
+
+    /// Node ids grouped by host, then by depth.
+    fn clusters(&self) -> BTreeMap<&str, BTreeMap<usize, Vec<usize>>> {
+        let mut clusters: BTreeMap<&str, BTreeMap<usize, Vec<usize>>> = BTreeMap::new();
+        for (id, node) in self.nodes.iter().enumerate() {
+            clusters
+                .entry(node.host.as_str())
+                .or_default()
+                .entry(node.depth)
+                .or_default()
+                .push(id);
+        }
+
+        clusters
+    }
+}
+
+fn collapse(url: &str, collapse: Collapse) -> (String, String, String) {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return (url.to_string(), url.to_string(), String::new());
+    };
+    let host = parsed.host_str().unwrap_or_default().to_string();
+
+    match collapse {
+        Collapse::None => {
+            let mut label = parsed.path().to_string();
+            if let Some(query) = parsed.query() {
+                label.push('?');
+                label.push_str(query);
+            }
+            (url.to_string(), label, host)
+        }
+        Collapse::Path => {
+            let key = format!("{}://{}{}", parsed.scheme(), host, parsed.path());
+            (key, parsed.path().to_string(), host)
+        }
+        Collapse::Host => (host.clone(), host.clone(), host),
+    }
+}
+
+impl Exporter {
+    pub fn dot(&self) -> String {
+        let diagram = Diagram::new(self);
+        let mut dot = String::new();
+
+        dot.push_str("digraph crawl {\n");
+        dot.push_str("  rankdir=LR;\n");
+        dot.push_str("  node [shape=box, style=rounded, fontsize=10];\n");
+
+        // Hosts are already the nodes when collapsing to hosts, so only rank them by depth.
+        let groups = match self.collapse {
+            Collapse::Host => BTreeMap::from([("", diagram.ranks())]),
+            _ => diagram.clusters(),
+        };
+        for (index, (host, depths)) in groups.into_iter().enumerate() {
+            let indent = match self.collapse {
+                Collapse::Host => "  ",
+                _ => {
+                    let _ = writeln!(dot, "  subgraph cluster_{} {{", index);
+                    let _ = writeln!(dot, "    label=\"{}\";", escape_dot(host));
+                    "    "
+                }
+            };
+            for ids in depths.values() {
+                for id in ids {
+                    let _ = writeln!(
+                        dot,
+                        "{}n{} [label=\"{}\"];",
+                        indent,
+                        id,
+                        escape_dot(&diagram.nodes[*id].label)
+                    );
+                }
+                let ranked = ids.iter().map(|id| format!("n{};", id)).collect::<Vec<_>>();
+                let _ = writeln!(dot, "{}{{ rank=same; {} }}", indent, ranked.join(" "));
+            }
+            if self.collapse != Collapse::Host {
+                dot.push_str("  }\n");
+            }
+        }
+
+        for ((source, target), count) in &diagram.edges {
+            match count {
+                1 => {
+                    let _ = writeln!(dot, "  n{} -> n{};", source, target);
+                }
+                count => {
+                    let _ = writeln!(
+                        dot,
+                        "  n{} -> n{} [label=\"{}\", penwidth={:.1}];",
+                        source,
+                        target,
+                        count,
+                        1.0 + (*count as f64).ln()
+                    );
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    pub fn mermaid(&self) -> String {
+        let diagram = Diagram::new(self);
+        let mut mermaid = String::new();
+
+        mermaid.push_str("flowchart LR\n");
+        for (index, (host, depths)) in diagram.clusters().into_iter().enumerate() {
+            let indent = match self.collapse {
+                Collapse::Host => "  ",
+                _ => {
+                    let _ = writeln!(
+                        mermaid,
+                        "  subgraph host{}[\"{}\"]",
+                        index,
+                        escape_mermaid(host)
+                    );
+                    "    "
+                }
+            };
+            for id in depths.values().flatten() {
+                let _ = writeln!(
+                    mermaid,
+                    "{}n{}[\"{}\"]",
+                    indent,
+                    id,
+                    escape_mermaid(&diagram.nodes[*id].label)
+                );
+            }
+            if self.collapse != Collapse::Host {
+                mermaid.push_str("  end\n");
+            }
+        }
+
+        for ((source, target), count) in &diagram.edges {
+            match count {
+                1 => {
+                    let _ = writeln!(mermaid, "  n{} --> n{}", source, target);
+                }
+                count => {
+                    let _ = writeln!(mermaid, "  n{} -->|{}| n{}", source, count, target);
+                }
+            }
+        }
+
+        mermaid
+    }
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(value: &str) -> String {
+    value
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Edge, Url};
+
+    fn exporter(collapse: Collapse) -> Exporter {
+        let edge =
+            |source: &str, target: &str| Edge::new(source.to_string(), target.to_string(), 1);
+
+        Exporter::new(
+            vec![Url::new("https://a.test/".to_string(), String::new(), 0)],
+            vec![
+                edge("https://a.test/", "https://a.test/say?q=\"hi\""),
+                edge("https://a.test/", "https://a.test/say?q=<b>"),
+                edge("https://a.test/", "https://b.test/"),
+            ],
+        )
+        .with_collapse(collapse)
+    }
+
+    #[test]
+    fn escapes_labels() {
+        assert_eq!(escape_dot(r#"a\"b"#), r#"a\\\"b"#);
+        assert_eq!(escape_mermaid("\"<b>\""), "#quot;#lt;b#gt;#quot;");
+
+        let dot = exporter(Collapse::None).dot();
+        assert!(dot.contains(r#"[label="/say?q=%22hi%22"];"#));
+        let mermaid = exporter(Collapse::None).mermaid();
+        assert!(mermaid.contains("[\"/say?q=%3Cb%3E\"]"));
+    }
+
+    #[test]
+    fn dot_clusters_by_host_and_ranks_by_depth() {
+        let dot = exporter(Collapse::None).dot();
+
+        assert!(dot.contains("  subgraph cluster_0 {\n    label=\"a.test\";\n"));
+        assert!(dot.contains("  subgraph cluster_1 {\n    label=\"b.test\";\n"));
+        assert!(dot.contains("    { rank=same; n0; }\n"));
+        assert!(dot.contains("    { rank=same; n1; n2; }\n"));
+        assert!(dot.contains("  n0 -> n3;\n"));
+    }
+
+    #[test]
+    fn collapsing_weighs_merged_edges() {
+        let dot = exporter(Collapse::Path).dot();
+        assert!(dot.contains("  n0 -> n1 [label=\"2\", penwidth=1.7];\n"));
+
+        let mermaid = exporter(Collapse::Host).mermaid();
+        assert_eq!(
+            mermaid,
+            "flowchart LR\n  n0[\"a.test\"]\n  n1[\"b.test\"]\n  n0 --> n1\n"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use super::Exporter;
+use crate::text::similarity;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// URLs whose bodies are byte-identical.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    pub body_hash: String,
+    pub urls: Vec<String>,
+}
+
+impl Exporter {
+    /// Groups of pages sharing a body hash, largest first.
+    pub fn duplicates(&self) -> Vec<DuplicateGroup> {
+        let mut groups: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for url in &self.urls {
+            if let Some(hash) = &url.body_hash {
+                groups.entry(hash).or_default().push(url.url.clone());
+            }
+        }
+
+        let mut duplicates = groups
+            .into_iter()
+            .filter(|(_, urls)| urls.len() > 1)
+            .map(|(hash, mut urls)| {
+                urls.sort();
+                DuplicateGroup {
+                    body_hash: hash.to_string(),
+                    urls,
+                }
+            })
+            .collect::<Vec<_>>();
+        duplicates.sort_by_key(|group| std::cmp::Reverse(group.urls.len()));
+
+        duplicates
+    }
+
+    pub fn duplicates_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.duplicates())
+            .context("Failed to serialize duplicates report")
+    }
+}
+
+/// Pages whose text fingerprints are within the similarity threshold of each other.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NearDuplicateCluster {
+    /// The lowest similarity between two pages linked in the cluster.
+    pub similarity: f64,
+    pub urls: Vec<String>,
+}
+
+impl Exporter {
+    /// Clusters of pages with similar text, largest first.
+    ///
+    /// Pages are linked when their fingerprints share at least `threshold` of their bits,
+    /// and clusters follow those links transitively.
+    pub fn near_duplicates(&self, threshold: f64) -> Vec<NearDuplicateCluster> {
+        let pages = self
+            .urls
+            .iter()
+            .filter_map(|url| Some((url.url.as_str(), url.simhash?)))
+            .collect::<Vec<_>>();
+        let max_distance = ((1.0 - threshold.clamp(0.0, 1.0)) * 64.0).floor() as u32;
+
+        // Fingerprints within `max_distance` bits agree on at least one of `max_distance + 1`
+        // blocks, so only pages sharing a block need to be compared.
+        let blocks = max_distance as usize + 1;
+        let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+        for (index, (_, fingerprint)) in pages.iter().enumerate() {
+            for block in 0..blocks {
+                buckets
+                    .entry((block, block_bits(*fingerprint, block, blocks)))
+                    .or_default()
+                    .push(index);
+            }
+        }
+
+        let mut parents = (0..pages.len()).collect::<Vec<_>>();
+        let mut lowest = vec![1.0f64; pages.len()];
+        for (&(block, _), members) in &buckets {
+            for (position, &a) in members.iter().enumerate() {
+                for &b in &members[position + 1..] {
+                    // Pages sharing several blocks are only compared in the first one.
+                    let (a_bits, b_bits) = (pages[a].1, pages[b].1);
+                    if (0..block).any(|earlier| {
+                        block_bits(a_bits, earlier, blocks) == block_bits(b_bits, earlier, blocks)
+                    }) {
+                        continue;
+                    }
+                    let distance = (pages[a].1 ^ pages[b].1).count_ones();
+                    if distance > max_distance {
+                        continue;
+                    }
+                    let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+                    let similarity = similarity(pages[a].1, pages[b].1)
+                        .min(lowest[root_a])
+                        .min(lowest[root_b]);
+                    parents[root_b] = root_a;
+                    lowest[root_a] = similarity;
+                }
+            }
+        }
+
+        let mut clusters: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for (index, (url, _)) in pages.iter().enumerate() {
+            let root = find(&mut parents, index);
+            clusters.entry(root).or_default().push(url.to_string());
+        }
+
+        let mut near_duplicates = clusters
+            .into_iter()
+            .filter(|(_, urls)| urls.len() > 1)
+            .map(|(root, mut urls)| {
+                urls.sort();
+                NearDuplicateCluster {
+                    similarity: lowest[root],
+                    urls,
+                }
+            })
+            .collect::<Vec<_>>();
+        near_duplicates.sort_by_key(|cluster| std::cmp::Reverse(cluster.urls.len()));
+
+        near_duplicates
+    }
+
+    pub fn near_duplicates_json(&self, threshold: f64) -> Result<String> {
+        serde_json::to_string_pretty(&self.near_duplicates(threshold))
+            .context("Failed to serialize near-duplicates report")
+    }
+}
+
+/// The bits of one of `blocks` roughly equal slices of a fingerprint.
+fn block_bits(fingerprint: u64, block: usize, blocks: usize) -> u64 {
+    let (start, end) = (block * 64 / blocks, (block + 1) * 64 / blocks);
+    let mask = match end - start {
+        64 => u64::MAX,
+        width => (1 << width) - 1,
+    };
+
+    fingerprint >> start & mask
+}
+
+fn find(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[index] = root;
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Url;
+
+    fn page(url: &str, body_hash: Option<&str>, simhash: Option<u64>) -> Url {
+        let mut page = Url::new(url.to_string(), String::new(), 1);
+        page.body_hash = body_hash.map(String::from);
+        page.simhash = simhash;
+        page
+    }
+
+    #[test]
+    fn groups_identical_bodies_largest_first() {
+        let exporter = Exporter::new(
+            vec![
+                page("https://a.test/c", Some("1"), None),
+                page("https://a.test/d", Some("2"), None),
+                page("https://a.test/a", Some("1"), None),
+                page("https://a.test/e", Some("2"), None),
+                page("https://a.test/b", Some("1"), None),
+                page("https://a.test/f", Some("3"), None),
+                page("https://a.test/g", None, None),
+            ],
+            Vec::new(),
+        );
+
+        let groups = exporter.duplicates();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].body_hash, "1");
+        assert_eq!(
+            groups[0].urls,
+            ["https://a.test/a", "https://a.test/b", "https://a.test/c"]
+        );
+        assert_eq!(groups[1].urls, ["https://a.test/d", "https://a.test/e"]);
+    }
+
+    #[test]
+    fn clusters_follow_similar_pages_transitively() {
+        // `b` is 3 bits from `a` and `c` is 3 bits from `b`, but 6 from `a`.
+        let a = 0xf0f0_f0f0_0000_0000u64;
+        let b = a ^ 0b111;
+        let c = b ^ 0b111 << 32;
+        let exporter = Exporter::new(
+            vec![
+                page("https://a.test/c", None, Some(c)),
+                page("https://a.test/a", None, Some(a)),
+                page("https://a.test/b", None, Some(b)),
+                page("https://a.test/far", None, Some(!a)),
+                page("https://a.test/twin", None, Some(!a ^ 1)),
+                page("https://a.test/empty", None, None),
+            ],
+            Vec::new(),
+        );
+
+        let clusters = exporter.near_duplicates(0.95);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(
+            clusters[0].urls,
+            ["https://a.test/a", "https://a.test/b", "https://a.test/c"]
+        );
+        assert_eq!(clusters[0].similarity, similarity(a, b));
+        assert_eq!(
+            clusters[1].urls,
+            ["https://a.test/far", "https://a.test/twin"]
+        );
+        assert_eq!(clusters[1].similarity, 63.0 / 64.0);
+
+        let clusters = exporter.near_duplicates(0.98);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            clusters[0].urls,
+            ["https://a.test/far", "https://a.test/twin"]
+        );
+
+        assert_eq!(exporter.near_duplicates(0.0)[0].urls.len(), 5);
+    }
+
+    #[test]
+    fn pages_sharing_every_block_are_clustered_once() {
+        let urls = (0..50)
+            .map(|index| page(&format!("https://a.test/{:02}", index), None, Some(42)))
+            .collect();
+        let exporter = Exporter::new(urls, Vec::new());
+
+        let clusters = exporter.near_duplicates(0.9);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].urls.len(), 50);
+        assert_eq!(clusters[0].urls[0], "https://a.test/00");
+        assert_eq!(clusters[0].similarity, 1.0);
+    }
+
+    #[test]
+    fn block_bits_cover_the_whole_fingerprint() {
+        let fingerprint = 0x0123_4567_89ab_cdefu64;
+        let blocks = 7;
+        let rebuilt = (0..blocks).fold(0, |rebuilt, block| {
+            rebuilt | block_bits(fingerprint, block, blocks) << (block * 64 / blocks)
+        });
+
+        assert_eq!(rebuilt, fingerprint);
+        assert_eq!(block_bits(fingerprint, 0, 1), fingerprint);
+    }
+}
@@ -0,0 +1,467 @@
+use crate::budget::BudgetKind;
+use crate::{Edge, Url};
+
+mod a11y;
+mod audit;
+mod canonical;
+mod diagram;
+mod duplicates;
+mod security;
+mod xml;
+
+pub use a11y::{A11yPage, A11yReport, A11yRuleSummary};
+pub use audit::{AuditCheck, AuditIssue, AuditSettings, Severity};
+pub use canonical::{CanonicalIssue, CanonicalProblem};
+pub use diagram::Collapse;
+pub use duplicates::{DuplicateGroup, NearDuplicateCluster};
+pub use security::HostSecurity;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::info;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// One row per crawled URL with the selected columns
+    Csv,
+    /// A directory with nodes.csv and edges.csv for Gephi's import wizard
+    GephiCsv,
+    /// A single document with crawl metadata, nodes and edges
+    Json,
+    /// One node or edge record per line, written while crawling
+    Jsonl,
+    /// GEXF graph for Gephi
+    Gexf,
+    /// GraphML graph for yEd, NetworkX and others
+    Graphml,
+    /// Graphviz DOT, clustered by host and ranked by depth
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    Url,
+    Source,
+    Depth,
+    Status,
+    ContentType,
+    Redirects,
+    Title,
+    BodyHash,
+    Simhash,
+    Description,
+    Robots,
+    Canonical,
+    Hreflang,
+    H1,
+    H2,
+    Lang,
+    WordCount,
+    Noindex,
+    Nofollow,
+    Skipped,
+}
+
+impl Column {
+    pub const DEFAULT: [Column; 3] = [Column::Url, Column::Source, Column::Depth];
+
+    fn header(&self) -> &'static str {
+        match self {
+            Column::Url => "Target",
+            Column::Source => "Source",
+            Column::Depth => "Depth",
+            Column::Status => "Status",
+            Column::ContentType => "Content-Type",
+            Column::Redirects => "Redirects",
+            Column::Title => "Title",
+            Column::BodyHash => "Body-Hash",
+            Column::Simhash => "SimHash",
+            Column::Description => "Description",
+            Column::Robots => "Robots",
+            Column::Canonical => "Canonical",
+            Column::Hreflang => "Hreflang",
+            Column::H1 => "H1",
+            Column::H2 => "H2",
+            Column::Lang => "Language",
+            Column::WordCount => "Word-Count",
+            Column::Noindex => "Noindex",
+            Column::Nofollow => "Nofollow",
+            Column::Skipped => "Skipped",
+        }
+    }
+
+    fn value(&self, url: &Url) -> String {
+        match self {
+            Column::Url => url.url.clone(),
+            Column::Source => url.found_at.clone(),
+            Column::Depth => url.depth.to_string(),
+            Column::Status => url.status.map(|s| s.to_string()).unwrap_or_default(),
+            Column::ContentType => url.content_type.clone().unwrap_or_default(),
+            Column::Redirects => url.redirects.join(" | "),
+            Column::Title => url.metadata.title.clone().unwrap_or_default(),
+            Column::BodyHash => url.body_hash.clone().unwrap_or_default(),
+            Column::Simhash => url
+                .simhash
+                .map(|simhash| format!("{:016x}", simhash))
+                .unwrap_or_default(),
+            Column::Description => url.metadata.description.clone().unwrap_or_default(),
+            Column::Robots => url.metadata.robots.clone().unwrap_or_default(),
+            Column::Canonical => url.metadata.canonical.clone().unwrap_or_default(),
+            Column::Hreflang => url
+                .metadata
+                .hreflang
+                .iter()
+                .map(|alternate| format!("{}={}", alternate.hreflang, alternate.url))
+                .collect::<Vec<_>>()
+                .join(" | "),
+            Column::H1 => url.metadata.h1.join(" | "),
+            Column::H2 => url.metadata.h2.join(" | "),
+            Column::Lang => url.metadata.lang.clone().unwrap_or_default(),
+            Column::WordCount => url
+                .metadata
+                .word_count
+                .map(|count| count.to_string())
+                .unwrap_or_default(),
+            Column::Noindex => url.noindex.to_string(),
+            Column::Nofollow => url.nofollow.to_string(),
+            Column::Skipped => url
+                .skipped
+                .map(|skipped| skipped.to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CrawlMetadata {
+    pub crawler_version: String,
+    pub start_url: String,
+    pub mode: String,
+    pub max_depth: u32,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub pages: usize,
+    pub edges: usize,
+    /// The budget that ended the crawl early, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped_by: Option<BudgetKind>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Record<'a> {
+    Node(&'a Url),
+    Edge(&'a Edge),
+    Metadata(&'a CrawlMetadata),
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    metadata: &'a CrawlMetadata,
+    nodes: &'a [Url],
+    edges: &'a [Edge],
+}
+
+pub struct Exporter {
+    pub urls: Vec<Url>,
+    pub edges: Vec<Edge>,
+    pub columns: Vec<Column>,
+    pub metadata: CrawlMetadata,
+    pub dynamic: bool,
+    pub collapse: Collapse,
+}
+
+impl Exporter {
+    pub fn new(urls: Vec<Url>, edges: Vec<Edge>) -> Self {
+        Exporter {
+            urls,
+            edges,
+            columns: Column::DEFAULT.to_vec(),
+            metadata: CrawlMetadata::default(),
+            dynamic: false,
+            collapse: Collapse::None,
+        }
+    }
+
+    /// Merges nodes in DOT and Mermaid output to keep diagrams of larger crawls readable.
+    pub fn with_collapse(mut self, collapse: Collapse) -> Self {
+        self.collapse = collapse;
+        self
+    }
+
+    /// Adds discovery timestamps to graph formats that support a timeline.
+    pub fn with_dynamic(mut self, dynamic: bool) -> Self {
+        self.dynamic = dynamic;
+        self
+    }
+
+    /// Edge endpoints that have no page record, such as links that were never visited.
+    fn dangling_nodes(&self) -> Vec<&str> {
+        let known: HashSet<&str> = self.urls.iter().map(|url| url.url.as_str()).collect();
+        let mut seen = HashSet::new();
+
+        self.edges
+            .iter()
+            .flat_map(|edge| [edge.source.as_str(), edge.target.as_str()])
+            .filter(|node| !known.contains(node) && seen.insert(*node))
+            .collect()
+    }
+
+    /// Leaves out noindex pages unless the robots policy indexes them.
+    pub fn with_indexes_noindex(mut self, indexes_noindex: bool) -> Self {
+        if !indexes_noindex {
+            self.urls.retain(|url| !url.noindex);
+        }
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: CrawlMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_columns(mut self, columns: &[Column]) -> Self {
+        if !columns.is_empty() {
+            self.columns = columns.to_vec();
+        }
+        self
+    }
+
+    pub fn csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        writer.write_record(self.columns.iter().map(Column::header))?;
+        for url in &self.urls {
+            writer.write_record(self.columns.iter().map(|column| column.value(url)))?;
+        }
+
+        into_string(writer)
+    }
+
+    pub fn nodes_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        writer.write_record(["Id", "Label", "Depth", "Status", "Content-Type", "Title"])?;
+        for url in &self.urls {
+            writer.write_record([
+                url.url.as_str(),
+                url.url.as_str(),
+                &url.depth.to_string(),
+                &Column::Status.value(url),
+                &Column::ContentType.value(url),
+                &Column::Title.value(url),
+            ])?;
+        }
+
+        into_string(writer)
+    }
+
+    pub fn edges_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        writer.write_record(["Source", "Target", "Type", "Depth", "Rel"])?;
+        for edge in &self.edges {
+            writer.write_record([
+                edge.source.as_str(),
+                edge.target.as_str(),
+                "Directed",
+                &edge.depth.to_string(),
+                &edge.rel.join(" "),
+            ])?;
+        }
+
+        into_string(writer)
+    }
+
+    pub fn json(&self) -> Result<String> {
+        let document = Document {
+            metadata: &self.metadata,
+            nodes: &self.urls,
+            edges: &self.edges,
+        };
+
+        serde_json::to_string_pretty(&document).context("Failed to serialize JSON data")
+    }
+
+    pub fn jsonl(&self) -> Result<String> {
+        let records = self
+            .urls
+            .iter()
+            .map(Record::Node)
+            .chain(self.edges.iter().map(Record::Edge))
+            .chain(std::iter::once(Record::Metadata(&self.metadata)));
+
+        let mut jsonl_data = String::new();
+        for record in records {
+            jsonl_data.push_str(&serde_json::to_string(&record)?);
+            jsonl_data.push('\n');
+        }
+
+        Ok(jsonl_data)
+    }
+
+    pub fn to_file(&self, format: Format, file_path: &str) -> Result<()> {
+        match format {
+            Format::Csv => write_file(file_path, &self.csv()?)?,
+            Format::GephiCsv => {
+                let dir = Path::new(file_path);
+                std::fs::create_dir_all(dir)
+                    .context(format!("Failed to create directory: {}", file_path))?;
+                write_file(dir.join("nodes.csv"), &self.nodes_csv()?)?;
+                write_file(dir.join("edges.csv"), &self.edges_csv()?)?;
+            }
+            Format::Json => write_file(file_path, &self.json()?)?,
+            Format::Jsonl => write_file(file_path, &self.jsonl()?)?,
+            Format::Gexf => write_file(file_path, &self.gexf())?,
+            Format::Graphml => write_file(file_path, &self.graphml())?,
+            Format::Dot => write_file(file_path, &self.dot())?,
+            Format::Mermaid => write_file(file_path, &self.mermaid())?,
+        }
+
+        Ok(())
+    }
+
+    pub fn to_stdout(&self) -> Result<()> {
+        let csv_data = self.csv()?;
+        println!("{}", csv_data);
+
+        Ok(())
+    }
+}
+
+fn into_string(writer: csv::Writer<Vec<u8>>) -> Result<String> {
+    let data = writer.into_inner().context("Failed to flush CSV data")?;
+
+    String::from_utf8(data).context("CSV data is not valid UTF-8")
+}
+
+fn write_file(file_path: impl AsRef<Path>, data: &str) -> Result<()> {
+    let file_path = file_path.as_ref();
+    std::fs::write(file_path, data).context(format!(
+        "Failed to write data to file: {}",
+        file_path.display()
+    ))?;
+    info!("Data written to file: {}", file_path.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, title: &str) -> Url {
+        let mut page = Url::new(url.to_string(), "https://a.test/".to_string(), 1);
+        page.status = Some(200);
+        page.metadata.title = Some(title.to_string());
+        page
+    }
+
+    #[test]
+    fn csv_quotes_fields_per_rfc_4180() {
+        let exporter = Exporter::new(
+            vec![page("https://a.test/?a=1,2", "Say \"hi\"\nthere")],
+            Vec::new(),
+        )
+        .with_columns(&[Column::Url, Column::Status, Column::Title]);
+
+        assert_eq!(
+            exporter.csv().unwrap(),
+            "Target,Status,Title\n\"https://a.test/?a=1,2\",200,\"Say \"\"hi\"\"\nthere\"\n"
+        );
+    }
+
+    #[test]
+    fn csv_defaults_to_url_source_and_depth() {
+        let exporter =
+            Exporter::new(vec![page("https://a.test/a", "A")], Vec::new()).with_columns(&[]);
+
+        assert_eq!(
+            exporter.csv().unwrap(),
+            "Target,Source,Depth\nhttps://a.test/a,https://a.test/,1\n"
+        );
+    }
+
+    #[test]
+    fn gephi_tables_list_nodes_and_directed_edges() {
+        let mut edge = Edge::new(
+            "https://a.test/".to_string(),
+            "https://a.test/a".to_string(),
+            1,
+        );
+        edge.rel = vec!["nofollow".to_string(), "ugc".to_string()];
+        let exporter = Exporter::new(vec![page("https://a.test/a", "A")], vec![edge]);
+
+        assert_eq!(
+            exporter.nodes_csv().unwrap(),
+            "Id,Label,Depth,Status,Content-Type,Title\nhttps://a.test/a,https://a.test/a,1,200,,A\n"
+        );
+        assert_eq!(
+            exporter.edges_csv().unwrap(),
+            "Source,Target,Type,Depth,Rel\nhttps://a.test/,https://a.test/a,Directed,1,nofollow ugc\n"
+        );
+    }
+
+    #[test]
+    fn json_holds_metadata_nodes_and_edges() {
+        let exporter = Exporter::new(
+            vec![page("https://a.test/a", "A")],
+            vec![Edge::new(
+                "https://a.test/".to_string(),
+                "https://a.test/a".to_string(),
+                1,
+            )],
+        )
+        .with_metadata(CrawlMetadata {
+            start_url: "https://a.test/".to_string(),
+            pages: 1,
+            ..Default::default()
+        });
+
+        let document: serde_json::Value = serde_json::from_str(&exporter.json().unwrap()).unwrap();
+        assert_eq!(document["metadata"]["start_url"], "https://a.test/");
+        assert_eq!(document["nodes"][0]["url"], "https://a.test/a");
+        assert_eq!(document["nodes"][0]["title"], "A");
+        assert_eq!(document["edges"][0]["target"], "https://a.test/a");
+    }
+
+    #[test]
+    fn jsonl_tags_each_record_and_ends_with_metadata() {
+        let exporter = Exporter::new(
+            vec![page("https://a.test/a", "A")],
+            vec![Edge::new(
+                "https://a.test/".to_string(),
+                "https://a.test/a".to_string(),
+                1,
+            )],
+        );
+
+        let types = exporter
+            .jsonl()
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let record: serde_json::Value = serde_json::from_str(line).unwrap();
+                record["type"].as_str().unwrap().to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["node", "edge", "metadata"]);
+    }
+
+    #[test]
+    fn noindex_pages_are_left_out_unless_indexed() {
+        let mut hidden = page("https://a.test/hidden", "Hidden");
+        hidden.noindex = true;
+        let urls = vec![page("https://a.test/a", "A"), hidden];
+
+        let exporter = Exporter::new(urls.clone(), Vec::new()).with_indexes_noindex(false);
+        assert_eq!(exporter.urls.len(), 1);
+        let exporter = Exporter::new(urls, Vec::new()).with_indexes_noindex(true);
+        assert_eq!(exporter.urls.len(), 2);
+    }
+}
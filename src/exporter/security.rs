@@ -0,0 +1,123 @@
+use super::Exporter;
+use crate::security::SecurityIssue;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Security findings of the HTTPS pages of one host.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HostSecurity {
+    pub host: String,
+    pub pages_checked: usize,
+    /// How many pages have each issue.
+    pub issues: BTreeMap<SecurityIssue, usize>,
+    /// Pages with at least one finding.
+    pub pages: Vec<String>,
+}
+
+impl Exporter {
+    /// Security findings summarized per host, for the hosts served over HTTPS.
+    pub fn security_report(&self) -> Vec<HostSecurity> {
+        let mut hosts: BTreeMap<String, HostSecurity> = BTreeMap::new();
+        for url in &self.urls {
+            // Redirected pages are checked at the URL that answered.
+            let answered = url.redirects.last().unwrap_or(&url.url);
+            let Ok(parsed) = url::Url::parse(answered) else {
+                continue;
+            };
+            if parsed.scheme() != "https" || !url.security_checked {
+                continue;
+            }
+            let host = parsed.host_str().unwrap_or_default().to_string();
+            let summary = hosts.entry(host.clone()).or_insert_with(|| HostSecurity {
+                host,
+                pages_checked: 0,
+                issues: BTreeMap::new(),
+                pages: Vec::new(),
+            });
+
+            summary.pages_checked += 1;
+            let mut issues = url
+                .security
+                .iter()
+                .map(|finding| finding.issue)
+                .collect::<Vec<_>>();
+            issues.sort();
+            issues.dedup();
+            for issue in issues {
+                *summary.issues.entry(issue).or_insert(0) += 1;
+            }
+            if !url.security.is_empty() {
+                summary.pages.push(url.url.clone());
+            }
+        }
+
+        hosts
+            .into_values()
+            .map(|mut host| {
+                host.pages.sort();
+                host
+            })
+            .collect()
+    }
+
+    pub fn security_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.security_report())
+            .context("Failed to serialize security report")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Url;
+    use crate::security::SecurityFinding;
+
+    fn page(url: &str, checked: bool, issues: &[SecurityIssue]) -> Url {
+        let mut page = Url::new(url.to_string(), url.to_string(), 1);
+        page.status = Some(200);
+        page.security_checked = checked;
+        page.security = issues
+            .iter()
+            .map(|issue| SecurityFinding {
+                issue: *issue,
+                detail: String::new(),
+            })
+            .collect();
+        page
+    }
+
+    #[test]
+    fn only_counts_checked_pages() {
+        let mut failed = page("https://a.test/error", false, &[]);
+        failed.status = Some(500);
+        let report = Exporter::new(
+            vec![
+                page("https://a.test/", true, &[SecurityIssue::MissingCsp]),
+                page(
+                    "https://a.test/b",
+                    true,
+                    &[SecurityIssue::MixedContent, SecurityIssue::MixedContent],
+                ),
+                page("https://a.test/file.pdf", false, &[]),
+                page("http://a.test/plain", false, &[]),
+                failed,
+            ],
+            Vec::new(),
+        )
+        .security_report();
+
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].host, "a.test");
+        assert_eq!(report[0].pages_checked, 2);
+        assert_eq!(
+            report[0].issues,
+            BTreeMap::from([
+                (SecurityIssue::MixedContent, 1),
+                (SecurityIssue::MissingCsp, 1)
+            ])
+        );
+        assert_eq!(report[0].pages, vec!["https://a.test/", "https://a.test/b"]);
+    }
+}
@@ -0,0 +1,315 @@
+use super::Exporter;
+use crate::Url;
+
+use std::fmt::Write;
+
+const NODE_ATTRIBUTES: [(&str, &str, &str); 15] = [
+    ("depth", "Depth", "integer"),
+    ("status", "Status", "integer"),
+    ("content_type", "Content-Type", "string"),
+    ("title", "Title", "string"),
+    ("found_at", "Found At", "string"),
+    ("body_hash", "Body Hash", "string"),
+    ("simhash", "SimHash", "string"),
+    ("description", "Description", "string"),
+    ("robots", "Robots", "string"),
+    ("canonical", "Canonical", "string"),
+    ("h1", "H1", "string"),
+    ("lang", "Language", "string"),
+    ("word_count", "Word Count", "integer"),
+    ("noindex", "Noindex", "boolean"),
+    ("nofollow", "Nofollow", "boolean"),
+];
+
+fn node_attribute(url: &Url, id: &str) -> Option<String> {
+    match id {
+        "depth" => Some(url.depth.to_string()),
+        "status" => url.status.map(|status| status.to_string()),
+        "content_type" => url.content_type.clone(),
+        "title" => url.metadata.title.clone(),
+        "found_at" => Some(url.found_at.clone()),
+        "body_hash" => url.body_hash.clone(),
+        "simhash" => url.simhash.map(|simhash| format!("{:016x}", simhash)),
+        "description" => url.metadata.description.clone(),
+        "robots" => url.metadata.robots.clone(),
+        "canonical" => url.metadata.canonical.clone(),
+        "h1" => url.metadata.h1.first().cloned(),
+        "lang" => url.metadata.lang.clone(),
+        "word_count" => url.metadata.word_count.map(|count| count.to_string()),
+        "noindex" => Some(url.noindex.to_string()),
+        "nofollow" => Some(url.nofollow.to_string()),
+        _ => None,
+    }
+}
+
+impl Exporter {
+    pub fn gexf(&self) -> String {
+        let mut gexf = String::new();
+
+        // Dynamic graphs place nodes and edges on a timeline, in seconds since the first discovery.
+        let origin = self
+            .urls
+            .iter()
+            .filter_map(|url| url.discovered_at)
+            .min()
+            .unwrap_or(0);
+        let spell = |discovered_at: Option<u64>| match (self.dynamic, discovered_at) {
+            (true, Some(time)) => format!(
+                " start=\"{:.3}\"",
+                time.saturating_sub(origin) as f64 / 1000.0
+            ),
+            _ => String::new(),
+        };
+
+        gexf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        gexf.push_str(
+            "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://gexf.net/1.3 http://gexf.net/1.3/gexf.xsd\" version=\"1.3\">\n",
+        );
+        let _ = writeln!(
+            gexf,
+            "  <meta>\n    <creator>ferris-the-crawler {}</creator>\n    <description>Crawl of {}</description>\n  </meta>",
+            escape(&self.metadata.crawler_version),
+            escape(&self.metadata.start_url)
+        );
+        match self.dynamic {
+            true => gexf.push_str(
+                "  <graph mode=\"dynamic\" defaultedgetype=\"directed\" timeformat=\"double\">\n",
+            ),
+            false => gexf.push_str("  <graph mode=\"static\" defaultedgetype=\"directed\">\n"),
+        }
+
+        gexf.push_str("    <attributes class=\"node\">\n");
+        for (index, (_, title, kind)) in NODE_ATTRIBUTES.iter().enumerate() {
+            let _ = writeln!(
+                gexf,
+                "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>",
+                index, title, kind
+            );
+        }
+        gexf.push_str("    </attributes>\n");
+        gexf.push_str("    <attributes class=\"edge\">\n");
+        gexf.push_str("      <attribute id=\"0\" title=\"Depth\" type=\"integer\"/>\n");
+        gexf.push_str("      <attribute id=\"1\" title=\"Rel\" type=\"string\"/>\n");
+        gexf.push_str("    </attributes>\n");
+
+        gexf.push_str("    <nodes>\n");
+        for url in &self.urls {
+            let _ = writeln!(
+                gexf,
+                "      <node id=\"{}\" label=\"{}\"{}>",
+                escape(&url.url),
+                escape(url.metadata.title.as_deref().unwrap_or(&url.url)),
+                spell(url.discovered_at)
+            );
+            gexf.push_str("        <attvalues>\n");
+            for (index, (id, _, _)) in NODE_ATTRIBUTES.iter().enumerate() {
+                if let Some(value) = node_attribute(url, id) {
+                    let _ = writeln!(
+                        gexf,
+                        "          <attvalue for=\"{}\" value=\"{}\"/>",
+                        index,
+                        escape(&value)
+                    );
+                }
+            }
+            gexf.push_str("        </attvalues>\n");
+            gexf.push_str("      </node>\n");
+        }
+        for node in self.dangling_nodes() {
+            let _ = writeln!(
+                gexf,
+                "      <node id=\"{}\" label=\"{}\"/>",
+                escape(node),
+                escape(node)
+            );
+        }
+        gexf.push_str("    </nodes>\n");
+
+        gexf.push_str("    <edges>\n");
+        for (index, edge) in self.edges.iter().enumerate() {
+            let _ = writeln!(
+                gexf,
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\"{}>",
+                index,
+                escape(&edge.source),
+                escape(&edge.target),
+                spell(edge.discovered_at)
+            );
+            let _ = writeln!(
+                gexf,
+                "        <attvalues>\n          <attvalue for=\"0\" value=\"{}\"/>",
+                edge.depth
+            );
+            if !edge.rel.is_empty() {
+                let _ = writeln!(
+                    gexf,
+                    "          <attvalue for=\"1\" value=\"{}\"/>",
+                    escape(&edge.rel.join(" "))
+                );
+            }
+            gexf.push_str("        </attvalues>\n");
+            gexf.push_str("      </edge>\n");
+        }
+        gexf.push_str("    </edges>\n");
+
+        gexf.push_str("  </graph>\n</gexf>\n");
+
+        gexf
+    }
+
+    pub fn graphml(&self) -> String {
+        let mut graphml = String::new();
+
+        graphml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        graphml.push_str(
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n",
+        );
+        graphml.push_str(
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        );
+        for (id, _, kind) in NODE_ATTRIBUTES {
+            let kind = match kind {
+                "integer" => "int",
+                kind => kind,
+            };
+            let _ = writeln!(
+                graphml,
+                "  <key id=\"{}\" for=\"node\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                id, id, kind
+            );
+        }
+        graphml.push_str(
+            "  <key id=\"edge_depth\" for=\"edge\" attr.name=\"depth\" attr.type=\"int\"/>\n",
+        );
+        graphml.push_str(
+            "  <key id=\"edge_rel\" for=\"edge\" attr.name=\"rel\" attr.type=\"string\"/>\n",
+        );
+        graphml.push_str("  <graph id=\"crawl\" edgedefault=\"directed\">\n");
+
+        for url in &self.urls {
+            let _ = writeln!(graphml, "    <node id=\"{}\">", escape(&url.url));
+            let _ = writeln!(
+                graphml,
+                "      <data key=\"label\">{}</data>",
+                escape(url.metadata.title.as_deref().unwrap_or(&url.url))
+            );
+            for (id, _, _) in NODE_ATTRIBUTES {
+                if let Some(value) = node_attribute(url, id) {
+                    let _ = writeln!(
+                        graphml,
+                        "      <data key=\"{}\">{}</data>",
+                        id,
+                        escape(&value)
+                    );
+                }
+            }
+            graphml.push_str("    </node>\n");
+        }
+        for node in self.dangling_nodes() {
+            let _ = writeln!(
+                graphml,
+                "    <node id=\"{}\">\n      <data key=\"label\">{}</data>\n    </node>",
+                escape(node),
+                escape(node)
+            );
+        }
+
+        for (index, edge) in self.edges.iter().enumerate() {
+            let _ = writeln!(
+                graphml,
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n      <data key=\"edge_depth\">{}</data>",
+                index,
+                escape(&edge.source),
+                escape(&edge.target),
+                edge.depth
+            );
+            if !edge.rel.is_empty() {
+                let _ = writeln!(
+                    graphml,
+                    "      <data key=\"edge_rel\">{}</data>",
+                    escape(&edge.rel.join(" "))
+                );
+            }
+            graphml.push_str("    </edge>\n");
+        }
+
+        graphml.push_str("  </graph>\n</graphml>\n");
+
+        graphml
+    }
+}
+
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than whitespace are not allowed in XML 1.0.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Edge;
+
+    fn exporter(dynamic: bool) -> Exporter {
+        let mut page = Url::new("https://a.test/?a=1&b=<2>".to_string(), String::new(), 0);
+        page.metadata.title = Some("Tom's \"page\"".to_string());
+        page.discovered_at = Some(1_000);
+        let mut edge = Edge::new(page.url.clone(), "https://a.test/next".to_string(), 1);
+        edge.discovered_at = Some(3_500);
+
+        Exporter::new(vec![page], vec![edge]).with_dynamic(dynamic)
+    }
+
+    #[test]
+    fn escapes_markup_and_drops_control_characters() {
+        assert_eq!(
+            escape("<a href=\"x\">&'\u{1}\t</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;\t&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn gexf_escapes_nodes_and_adds_linked_pages() {
+        let gexf = exporter(false).gexf();
+
+        assert!(gexf.contains(
+            "<node id=\"https://a.test/?a=1&amp;b=&lt;2&gt;\" label=\"Tom&apos;s &quot;page&quot;\">"
+        ));
+        assert!(gexf.contains("<node id=\"https://a.test/next\" label=\"https://a.test/next\"/>"));
+        assert!(gexf.contains("<graph mode=\"static\""));
+        assert!(!gexf.contains("start="));
+    }
+
+    #[test]
+    fn dynamic_gexf_places_nodes_on_a_timeline() {
+        let gexf = exporter(true).gexf();
+
+        assert!(gexf.contains("<graph mode=\"dynamic\""));
+        assert!(gexf.contains("label=\"Tom&apos;s &quot;page&quot;\" start=\"0.000\">"));
+        assert!(gexf.contains("target=\"https://a.test/next\" start=\"2.500\">"));
+    }
+
+    #[test]
+    fn graphml_escapes_ids_and_data() {
+        let graphml = exporter(false).graphml();
+
+        assert!(graphml.contains("<node id=\"https://a.test/?a=1&amp;b=&lt;2&gt;\">"));
+        assert!(graphml.contains("<data key=\"label\">Tom&apos;s &quot;page&quot;</data>"));
+        assert!(graphml.contains("<data key=\"depth\">0</data>"));
+        assert!(graphml.contains(
+            "source=\"https://a.test/?a=1&amp;b=&lt;2&gt;\" target=\"https://a.test/next\">"
+        ));
+    }
+}
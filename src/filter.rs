@@ -0,0 +1,361 @@
+use dashmap::DashSet;
+use regex::Regex;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Include,
+    Exclude,
+}
+
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    /// The URL contains the text
+    Substring(String),
+    /// The regex matches somewhere in the URL
+    Regex(Regex),
+    /// The glob matches the whole URL, compiled to a regex
+    Glob(String, Regex),
+}
+
+impl Pattern {
+    fn matches(&self, url: &str) -> bool {
+        match self {
+            Pattern::Substring(text) => url.contains(text.as_str()),
+            Pattern::Regex(regex) | Pattern::Glob(_, regex) => regex.is_match(url),
+        }
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Substring(text) => write!(f, "{}", text),
+            Pattern::Regex(regex) => write!(f, "re:{}", regex.as_str()),
+            Pattern::Glob(glob, _) => write!(f, "glob:{}", glob),
+        }
+    }
+}
+
+/// A `+ PATTERN` or `- PATTERN` rule including or excluding the URLs it matches.
+///
+/// Patterns are substrings by default, or regexes and globs with a `re:` or `glob:` prefix.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub action: Action,
+    pub pattern: Pattern,
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid filter rule (expected + PATTERN or - PATTERN): {}",
+                s
+            )
+        };
+        let s = s.trim_start();
+        let action = match s.chars().next() {
+            Some('+') => Action::Include,
+            Some('-') => Action::Exclude,
+            _ => return Err(invalid()),
+        };
+        let pattern = s[1..].trim();
+        if pattern.is_empty() {
+            return Err(invalid());
+        }
+
+        let pattern = if let Some(regex) = pattern.strip_prefix("re:") {
+            Pattern::Regex(
+                Regex::new(regex).map_err(|e| format!("Invalid regex {}: {}", regex, e))?,
+            )
+        } else if let Some(glob) = pattern.strip_prefix("glob:") {
+            let regex = Regex::new(&glob_to_regex(glob))
+                .map_err(|e| format!("Invalid glob {}: {}", glob, e))?;
+            Pattern::Glob(glob.to_string(), regex)
+        } else {
+            Pattern::Substring(pattern.to_string())
+        };
+
+        Ok(Rule { action, pattern })
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.action {
+            Action::Include => write!(f, "+ {}", self.pattern),
+            Action::Exclude => write!(f, "- {}", self.pattern),
+        }
+    }
+}
+
+/// Ordered include and exclude rules where the first matching rule decides, like rsync.
+///
+/// URLs no rule matches are included, unless there are include rules: then only
+/// what they match is crawled.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    rules: Vec<Rule>,
+    /// URLs matched by each rule, and by none in the last slot.
+    counts: Arc<Vec<AtomicUsize>>,
+    seen: Arc<DashSet<u64>>,
+}
+
+impl Filter {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        let counts = (0..=rules.len()).map(|_| AtomicUsize::new(0)).collect();
+
+        Filter {
+            rules,
+            counts: Arc::new(counts),
+            seen: Arc::new(DashSet::new()),
+        }
+    }
+
+    /// The rules from `--rule`, followed by the `-i` substrings to exclude and
+    /// the `-f` substrings to include.
+    pub fn from_args(rules: &[Rule], filters: &[String], ignore: &[String]) -> Self {
+        let substrings = |action, patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| Rule {
+                    action,
+                    pattern: Pattern::Substring(pattern.clone()),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut all = rules.to_vec();
+        all.extend(substrings(Action::Exclude, ignore));
+        all.extend(substrings(Action::Include, filters));
+
+        Filter::new(all)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The index of the first rule matching a URL.
+    fn rule_for(&self, url: &str) -> Option<usize> {
+        self.rules.iter().position(|rule| rule.pattern.matches(url))
+    }
+
+    fn default_action(&self) -> Action {
+        match self.rules.iter().any(|rule| rule.action == Action::Include) {
+            true => Action::Exclude,
+            false => Action::Include,
+        }
+    }
+
+    /// Whether a URL is crawled, counting it for the rule that decided.
+    pub fn allows(&self, url: &str) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let rule = self.rule_for(url);
+        if self.seen.insert(hash(url)) {
+            self.counts[rule.unwrap_or(self.rules.len())].fetch_add(1, Ordering::Relaxed);
+        }
+
+        match rule {
+            Some(index) => self.rules[index].action == Action::Include,
+            None => self.default_action() == Action::Include,
+        }
+    }
+
+    /// Tells which rule allows or rejects a URL.
+    pub fn explain(&self, url: &str) -> String {
+        let verdict = |action| match action {
+            Action::Include => "allowed",
+            Action::Exclude => "rejected",
+        };
+
+        match self.rule_for(url) {
+            Some(index) => {
+                let rule = &self.rules[index];
+                format!(
+                    "{} {} by rule {} ({})",
+                    verdict(rule.action),
+                    url,
+                    index + 1,
+                    rule
+                )
+            }
+            None if self.is_empty() => format!("allowed {}, there are no rules", url),
+            None => match self.default_action() {
+                Action::Include => format!("allowed {}, no rule matched", url),
+                Action::Exclude => format!("rejected {}, no include rule matched", url),
+            },
+        }
+    }
+
+    /// How many distinct URLs each rule decided on, and how many no rule matched.
+    pub fn counts(&self) -> (Vec<(&Rule, usize)>, usize) {
+        let counts = self
+            .rules
+            .iter()
+            .zip(self.counts.iter())
+            .map(|(rule, count)| (rule, count.load(Ordering::Relaxed)))
+            .collect();
+        let unmatched = self.counts[self.rules.len()].load(Ordering::Relaxed);
+
+        (counts, unmatched)
+    }
+}
+
+/// Translates a glob to an anchored regex: `*` stays within a path segment,
+/// `**` crosses segments and `?` is one character other than `/`.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+
+    regex
+}
+
+fn hash(url: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(rules: &[&str]) -> Filter {
+        Filter::new(rules.iter().map(|rule| rule.parse().unwrap()).collect())
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rule = "+ /blog/".parse::<Rule>().unwrap();
+        assert_eq!(rule.action, Action::Include);
+        assert_eq!(rule.to_string(), "+ /blog/");
+        assert_eq!(
+            " -re:\\.pdf$".parse::<Rule>().unwrap().to_string(),
+            "- re:\\.pdf$"
+        );
+        assert_eq!(
+            "-glob:**/tags/*".parse::<Rule>().unwrap().to_string(),
+            "- glob:**/tags/*"
+        );
+        assert!("/blog/".parse::<Rule>().is_err());
+        assert!("+  ".parse::<Rule>().is_err());
+        assert!("+ re:(".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn globs_stay_within_segments() {
+        let glob = Regex::new(&glob_to_regex("https://a.test/*/p?ge.html")).unwrap();
+        assert!(glob.is_match("https://a.test/docs/page.html"));
+        assert!(!glob.is_match("https://a.test/docs/v1/page.html"));
+        assert!(!glob.is_match("https://a.test/docs/p/ge.html"));
+
+        let glob = Regex::new(&glob_to_regex("**.pdf")).unwrap();
+        assert!(glob.is_match("https://a.test/docs/v1/manual.pdf"));
+        assert!(!glob.is_match("https://a.test/manual.pdf?download"));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let filter = filter(&["+ /blog/keep", "- /blog/", "- re:\\.pdf$"]);
+
+        assert!(filter.allows("https://a.test/blog/keep-this"));
+        assert!(!filter.allows("https://a.test/blog/other"));
+        assert!(!filter.allows("https://a.test/manual.pdf"));
+        // With an include rule, what no rule matches is left out.
+        assert!(!filter.allows("https://a.test/about"));
+
+        let filter = self::filter(&["- /blog/", "+ /blog/keep"]);
+        assert!(!filter.allows("https://a.test/blog/keep-this"));
+    }
+
+    #[test]
+    fn unmatched_urls_are_allowed_without_include_rules() {
+        assert!(Filter::new(Vec::new()).allows("https://a.test/"));
+        assert!(filter(&["- /private"]).allows("https://a.test/"));
+    }
+
+    #[test]
+    fn from_args_puts_rules_before_ignores_and_filters() {
+        let rules = ["+ /admin/public".parse().unwrap()];
+        let filter = Filter::from_args(&rules, &["a.test".to_string()], &["/admin".to_string()]);
+
+        assert!(filter.allows("https://a.test/admin/public"));
+        assert!(!filter.allows("https://a.test/admin/secret"));
+        assert!(filter.allows("https://a.test/"));
+        assert!(!filter.allows("https://b.test/"));
+    }
+
+    #[test]
+    fn explains_decisions() {
+        let filter = filter(&["- /blog/", "+ a.test"]);
+
+        assert_eq!(
+            filter.explain("https://a.test/blog/x"),
+            "rejected https://a.test/blog/x by rule 1 (- /blog/)"
+        );
+        assert_eq!(
+            filter.explain("https://a.test/"),
+            "allowed https://a.test/ by rule 2 (+ a.test)"
+        );
+        assert_eq!(
+            filter.explain("https://b.test/"),
+            "rejected https://b.test/, no include rule matched"
+        );
+        assert_eq!(
+            self::filter(&["- /blog/"]).explain("https://b.test/"),
+            "allowed https://b.test/, no rule matched"
+        );
+        assert_eq!(
+            Filter::new(Vec::new()).explain("https://b.test/"),
+            "allowed https://b.test/, there are no rules"
+        );
+    }
+
+    #[test]
+    fn counts_distinct_urls_per_rule() {
+        let filter = filter(&["- /blog/", "+ a.test"]);
+        for url in [
+            "https://a.test/blog/1",
+            "https://a.test/blog/2",
+            "https://a.test/blog/1",
+            "https://a.test/",
+            "https://b.test/",
+            "https://b.test/",
+        ] {
+            filter.allows(url);
+        }
+
+        let (counts, unmatched) = filter.counts();
+        let counts = counts
+            .into_iter()
+            .map(|(rule, count)| (rule.to_string(), count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![("- /blog/".to_string(), 2), ("+ a.test".to_string(), 1)]
+        );
+        assert_eq!(unmatched, 1);
+    }
+}
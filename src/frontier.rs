@@ -0,0 +1,646 @@
+use crate::Link;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tokio::sync::{Mutex, Notify};
+use tracing::{error, info};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    /// Breadth-first: every page of a depth before the next depth
+    #[default]
+    Bfs,
+    /// Depth-first: the most recently found link first
+    Dfs,
+    /// Best-first: the link with the highest score first
+    BestFirst,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Score {
+    /// Links to pages with more known in-links first
+    #[default]
+    InLinks,
+    /// URLs with fewer path segments first
+    UrlDepth,
+    /// URLs with a higher priority in the sitemap first
+    Sitemap,
+}
+
+/// A link waiting in the frontier, as seen by a [`Scorer`].
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate<'a> {
+    pub url: &'a str,
+    pub from: &'a str,
+    pub depth: usize,
+    /// Links to the URL queued so far, this one included.
+    pub in_links: usize,
+}
+
+/// Ranks links for best-first crawls, higher scores are crawled first.
+pub trait Scorer: Send + Sync {
+    fn score(&self, candidate: &Candidate) -> f64;
+}
+
+pub struct InLinks;
+
+impl Scorer for InLinks {
+    fn score(&self, candidate: &Candidate) -> f64 {
+        candidate.in_links as f64
+    }
+}
+
+pub struct UrlDepth;
+
+impl Scorer for UrlDepth {
+    fn score(&self, candidate: &Candidate) -> f64 {
+        let segments = url::Url::parse(candidate.url)
+            .ok()
+            .and_then(|url| Some(url.path_segments()?.filter(|s| !s.is_empty()).count()))
+            .unwrap_or(0);
+
+        -(segments as f64)
+    }
+}
+
+/// Scores URLs by their sitemap `<priority>`, 0.5 when a listed URL has none and 0 when unlisted.
+pub struct SitemapPriority {
+    priorities: HashMap<String, f64>,
+}
+
+impl SitemapPriority {
+    pub fn new(entries: impl IntoIterator<Item = (String, Option<f64>)>) -> Self {
+        SitemapPriority {
+            priorities: entries
+                .into_iter()
+                .map(|(url, priority)| {
+                    // Listed the way links are resolved, so both spellings meet.
+                    let url = url::Url::parse(&url).map_or(url, |url| url.to_string());
+                    (url, priority.unwrap_or(0.5))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Scorer for SitemapPriority {
+    fn score(&self, candidate: &Candidate) -> f64 {
+        self.priorities.get(candidate.url).copied().unwrap_or(0.0)
+    }
+}
+
+/// A link queued for crawling, with the page it was found on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Queued {
+    pub link: Link,
+    pub from: String,
+    pub depth: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Compared first, higher is popped first.
+    rank: f64,
+    /// Breaks ties, higher is popped first.
+    tiebreak: i64,
+    queued: Queued,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank
+            .total_cmp(&other.rank)
+            .then(self.tiebreak.cmp(&other.tiebreak))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    queue: BinaryHeap<Entry>,
+    /// Incremented for every push, orders links found at the same rank.
+    pushed: i64,
+    /// Links popped and not done yet, which may still queue more links.
+    in_flight: usize,
+    spill: Option<Spill>,
+}
+
+impl State {
+    /// Whether the best link is in a segment rather than in memory.
+    fn next_is_spilled(&self) -> bool {
+        let Some(spill) = &self.spill else {
+            return false;
+        };
+        match (self.queue.peek(), spill.best()) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(queued), Some(spilled)) => spilled > queued,
+        }
+    }
+
+    async fn pop(&mut self) -> Option<Entry> {
+        match self.next_is_spilled() {
+            true => self.spill.as_mut()?.pop().await,
+            false => self.queue.pop(),
+        }
+    }
+
+    /// Moves the lower half of the queue to a segment once it outgrows the memory limit.
+    async fn spill_if_full(&mut self) {
+        let Some(spill) = &mut self.spill else {
+            return;
+        };
+        if self.queue.len() <= spill.max_in_memory {
+            return;
+        }
+
+        let mut entries = std::mem::take(&mut self.queue).into_sorted_vec();
+        let kept = entries.split_off(entries.len() - spill.max_in_memory / 2);
+        self.queue = kept.into();
+        if let Err((e, entries)) = spill.write_segment(entries).await {
+            error!("Failed to spill the frontier to disk: {:?}", e);
+            self.queue.extend(entries);
+        }
+    }
+}
+
+/// Links moved out of memory into segment files, each sorted best first.
+///
+/// Only the next link of every segment is held in memory, so merging the
+/// segments with the in-memory queue keeps the crawl order exact. Segments are
+/// written and read on the blocking thread pool.
+struct Spill {
+    dir: PathBuf,
+    max_in_memory: usize,
+    /// Tells apart the segments of frontiers sharing a directory.
+    prefix: String,
+    written: usize,
+    segments: Vec<Segment>,
+    len: usize,
+}
+
+struct Segment {
+    path: PathBuf,
+    reader: BufReader<File>,
+    head: Entry,
+}
+
+impl Segment {
+    /// Replaces the head with the next entry, returning the old head and whether
+    /// the segment has more entries.
+    fn advance(&mut self) -> (Option<Entry>, bool) {
+        match read_entry(&mut self.reader) {
+            Ok(Some(next)) => (Some(std::mem::replace(&mut self.head, next)), true),
+            result => {
+                if let Err(e) = result {
+                    error!(
+                        "Dropping unreadable frontier segment {:?}: {:?}",
+                        self.path, e
+                    );
+                }
+                (None, false)
+            }
+        }
+    }
+}
+
+impl Spill {
+    fn best(&self) -> Option<&Entry> {
+        self.segments.iter().map(|segment| &segment.head).max()
+    }
+
+    async fn pop(&mut self) -> Option<Entry> {
+        let index = self
+            .segments
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.head.cmp(&b.head))
+            .map(|(index, _)| index)?;
+        self.len -= 1;
+
+        // A line already buffered is read in place, only refills touch the disk.
+        let mut segment = self.segments.swap_remove(index);
+        let (segment, (popped, more)) = match segment.reader.buffer().contains(&b'\n') {
+            true => {
+                let advanced = segment.advance();
+                (segment, advanced)
+            }
+            false => tokio::task::spawn_blocking(move || {
+                let advanced = segment.advance();
+                (segment, advanced)
+            })
+            .await
+            .ok()?,
+        };
+        if more {
+            self.segments.push(segment);
+            return popped;
+        }
+
+        let _ = std::fs::remove_file(&segment.path);
+        Some(segment.head)
+    }
+
+    /// Writes entries sorted worst first to a new segment, best first.
+    ///
+    /// The entries are handed back with the error if the segment could not be written.
+    async fn write_segment(
+        &mut self,
+        entries: Vec<Entry>,
+    ) -> std::result::Result<(), (anyhow::Error, Vec<Entry>)> {
+        let path = self
+            .dir
+            .join(format!("{}-{}.jsonl", self.prefix, self.written));
+        self.written += 1;
+        let count = entries.len();
+
+        let dir = self.dir.clone();
+        let write_path = path.clone();
+        let written = tokio::task::spawn_blocking(move || {
+            let segment = write_segment(&dir, &write_path, &entries);
+            if segment.is_err() {
+                let _ = std::fs::remove_file(&write_path);
+            }
+            (segment, entries)
+        })
+        .await;
+        let segment = match written {
+            Ok((Ok(segment), _)) => segment,
+            Ok((Err(e), entries)) => return Err((e, entries)),
+            Err(e) => return Err((e.into(), Vec::new())),
+        };
+
+        info!("Spilled {} links to {:?}", count, path);
+        self.len += count;
+        self.segments.push(segment);
+
+        Ok(())
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        for segment in &self.segments {
+            let _ = std::fs::remove_file(&segment.path);
+        }
+    }
+}
+
+fn write_segment(dir: &Path, path: &Path, entries: &[Entry]) -> Result<Segment> {
+    std::fs::create_dir_all(dir).context(format!("Failed to create directory {:?}", dir))?;
+    let mut writer =
+        BufWriter::new(File::create(path).context(format!("Failed to create segment {:?}", path))?);
+    for entry in entries.iter().rev() {
+        serde_json::to_writer(&mut writer, entry)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let head = read_entry(&mut reader)?.context("Empty frontier segment")?;
+
+    Ok(Segment {
+        path: path.to_path_buf(),
+        reader,
+        head,
+    })
+}
+
+fn read_entry(reader: &mut BufReader<File>) -> Result<Option<Entry>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+/// The links left to crawl, handed out to workers in the chosen order.
+///
+/// The crawl is over once the frontier is empty and no worker is still
+/// processing a link, as that is the only way new links get queued.
+pub struct Frontier {
+    order: Order,
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+impl Frontier {
+    pub fn new(order: Order) -> Self {
+        Frontier {
+            order,
+            state: Mutex::new(State::default()),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Keeps at most `max_in_memory` links in memory, spilling the rest to segment files in `dir`.
+    pub fn with_spill(mut self, dir: &Path, max_in_memory: usize) -> Self {
+        self.state.get_mut().spill = Some(Spill {
+            dir: dir.to_path_buf(),
+            max_in_memory: max_in_memory.max(2),
+            prefix: format!("frontier-{}", uuid::Uuid::new_v4()),
+            written: 0,
+            segments: Vec::new(),
+            len: 0,
+        });
+        self
+    }
+
+    pub async fn push(&self, queued: Queued, score: f64) {
+        let mut state = self.state.lock().await;
+        state.pushed += 1;
+        let (rank, tiebreak) = match self.order {
+            Order::Bfs => (-(queued.depth as f64), -state.pushed),
+            Order::Dfs => (0.0, state.pushed),
+            Order::BestFirst => (score, -state.pushed),
+        };
+        state.queue.push(Entry {
+            rank,
+            tiebreak,
+            queued,
+        });
+        state.spill_if_full().await;
+        drop(state);
+
+        self.notify.notify_waiters();
+    }
+
+    /// The next link to crawl, waiting for links while other workers are busy.
+    ///
+    /// Returns `None` once the crawl is over. Every link returned must be
+    /// followed by a call to [`Frontier::done`].
+    pub async fn pop(&self) -> Option<Queued> {
+        loop {
+            let notified = self.notify.notified();
+            {
+                let mut state = self.state.lock().await;
+                if let Some(entry) = state.pop().await {
+                    state.in_flight += 1;
+                    return Some(entry.queued);
+                }
+                if state.in_flight == 0 {
+                    drop(state);
+                    self.notify.notify_waiters();
+                    return None;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    pub async fn done(&self) {
+        self.state.lock().await.in_flight -= 1;
+        self.notify.notify_waiters();
+    }
+
+    pub async fn len(&self) -> usize {
+        let state = self.state.lock().await;
+        state.queue.len() + state.spill.as_ref().map_or(0, |spill| spill.len)
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn queued(url: &str, depth: usize) -> Queued {
+        Queued {
+            link: Link::new(url.to_string()),
+            from: String::new(),
+            depth,
+        }
+    }
+
+    /// Pops every link, finishing each before taking the next.
+    async fn drain(frontier: &Frontier) -> Vec<String> {
+        let mut urls = Vec::new();
+        while let Some(queued) = frontier.pop().await {
+            urls.push(queued.link.url);
+            frontier.done().await;
+        }
+
+        urls
+    }
+
+    fn candidate(url: &str, in_links: usize) -> Candidate<'_> {
+        Candidate {
+            url,
+            from: "",
+            depth: 1,
+            in_links,
+        }
+    }
+
+    #[test]
+    fn bfs_takes_shallow_links_first_in_queued_order() {
+        block_on(async {
+            let frontier = Frontier::new(Order::Bfs);
+            frontier.push(queued("deep", 2), 0.0).await;
+            frontier.push(queued("a", 1), 0.0).await;
+            frontier.push(queued("b", 1), 0.0).await;
+            frontier.push(queued("start", 0), 0.0).await;
+
+            assert_eq!(frontier.len().await, 4);
+            assert_eq!(drain(&frontier).await, ["start", "a", "b", "deep"]);
+            assert!(frontier.is_empty().await);
+        });
+    }
+
+    #[test]
+    fn dfs_takes_the_latest_link_first() {
+        block_on(async {
+            let frontier = Frontier::new(Order::Dfs);
+            frontier.push(queued("a", 1), 0.0).await;
+            frontier.push(queued("b", 1), 0.0).await;
+            frontier.push(queued("c", 2), 0.0).await;
+
+            assert_eq!(drain(&frontier).await, ["c", "b", "a"]);
+        });
+    }
+
+    #[test]
+    fn best_first_takes_the_highest_score_first() {
+        block_on(async {
+            let frontier = Frontier::new(Order::BestFirst);
+            frontier.push(queued("low", 1), 1.0).await;
+            frontier.push(queued("high", 3), 5.0).await;
+            frontier.push(queued("tie-first", 1), 2.0).await;
+            frontier.push(queued("tie-second", 1), 2.0).await;
+
+            assert_eq!(
+                drain(&frontier).await,
+                ["high", "tie-first", "tie-second", "low"]
+            );
+        });
+    }
+
+    #[test]
+    fn links_queued_while_in_flight_are_handed_out() {
+        block_on(async {
+            let frontier = Frontier::new(Order::Bfs);
+            frontier.push(queued("start", 0), 0.0).await;
+
+            let start = frontier.pop().await.unwrap();
+            assert_eq!(start.link.url, "start");
+            frontier.push(queued("found", 1), 0.0).await;
+            frontier.done().await;
+
+            assert_eq!(drain(&frontier).await, ["found"]);
+        });
+    }
+
+    #[test]
+    fn spilled_links_keep_their_order() {
+        let dir = std::env::temp_dir().join(format!("frontier-{}", uuid::Uuid::new_v4()));
+        block_on(async {
+            let frontier = Frontier::new(Order::BestFirst).with_spill(&dir, 4);
+            let scores = [
+                3, 17, 8, 1, 12, 20, 5, 14, 9, 2, 19, 6, 11, 16, 4, 10, 7, 18, 13, 15,
+            ];
+            for score in scores {
+                frontier
+                    .push(queued(&score.to_string(), 1), score as f64)
+                    .await;
+            }
+
+            assert_eq!(frontier.len().await, scores.len());
+            assert!(std::fs::read_dir(&dir).unwrap().count() > 1);
+            let expected = (1..=20)
+                .rev()
+                .map(|score| score.to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(drain(&frontier).await, expected);
+            assert!(frontier.is_empty().await);
+            // Segments are removed once read.
+            assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+            frontier.push(queued("again", 1), 0.0).await;
+            assert_eq!(drain(&frontier).await, ["again"]);
+        });
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn segments_larger_than_the_read_buffer_are_refilled() {
+        let dir = std::env::temp_dir().join(format!("frontier-{}", uuid::Uuid::new_v4()));
+        block_on(async {
+            let frontier = Frontier::new(Order::Bfs).with_spill(&dir, 100);
+            // Long URLs, so a segment spans many refills of its reader.
+            let url = |index: usize| format!("https://a.test/{}/{}", "x".repeat(200), index);
+            for index in 0..1000 {
+                frontier.push(queued(&url(index), index), 0.0).await;
+            }
+
+            let expected = (0..1000).map(url).collect::<Vec<_>>();
+            assert_eq!(drain(&frontier).await, expected);
+        });
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_workers_take_every_spilled_link_once() {
+        let dir = std::env::temp_dir().join(format!("frontier-{}", uuid::Uuid::new_v4()));
+        let mut urls = block_on(async {
+            let frontier = Arc::new(Frontier::new(Order::Dfs).with_spill(&dir, 8));
+            for index in 0..100 {
+                frontier.push(queued(&index.to_string(), 0), 0.0).await;
+            }
+            assert!(std::fs::read_dir(&dir).unwrap().count() > 0);
+
+            let workers = (0..4)
+                .map(|_| {
+                    let frontier = frontier.clone();
+                    tokio::spawn(async move {
+                        let mut urls = Vec::new();
+                        while let Some(queued) = frontier.pop().await {
+                            let index = queued.link.url.parse::<usize>().unwrap();
+                            // The first links each find one more, queued while others are popped.
+                            if index < 100 {
+                                let next = (index + 100).to_string();
+                                frontier.push(self::queued(&next, 1), 0.0).await;
+                            }
+                            urls.push(index);
+                            frontier.done().await;
+                        }
+                        urls
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut urls = Vec::new();
+            for worker in workers {
+                urls.extend(worker.await.unwrap());
+            }
+            urls
+        });
+
+        urls.sort();
+        assert_eq!(urls, (0..200).collect::<Vec<_>>());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn segments_are_removed_with_the_frontier() {
+        let dir = std::env::temp_dir().join(format!("frontier-{}", uuid::Uuid::new_v4()));
+        let frontier = Frontier::new(Order::Bfs).with_spill(&dir, 2);
+        block_on(async {
+            for index in 0..10 {
+                frontier.push(queued(&index.to_string(), index), 0.0).await;
+            }
+        });
+        assert!(std::fs::read_dir(&dir).unwrap().count() > 0);
+
+        drop(frontier);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scorers() {
+        assert_eq!(InLinks.score(&candidate("https://a.test/", 3)), 3.0);
+        assert_eq!(UrlDepth.score(&candidate("https://a.test/", 1)), 0.0);
+        assert_eq!(UrlDepth.score(&candidate("https://a.test/a/b/", 1)), -2.0);
+
+        let sitemap = SitemapPriority::new([
+            ("https://a.test".to_string(), Some(0.9)),
+            ("https://a.test/page".to_string(), None),
+        ]);
+        assert_eq!(sitemap.score(&candidate("https://a.test/", 1)), 0.9);
+        assert_eq!(sitemap.score(&candidate("https://a.test/page", 1)), 0.5);
+        assert_eq!(sitemap.score(&candidate("https://a.test/other", 1)), 0.0);
+    }
+}
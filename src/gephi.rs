@@ -1,5 +1,7 @@
+use crate::GraphEvent;
+use crate::style::{EdgeStyle, NodeStyle};
+
 use reqwest::Client;
-use serde_json::json;
 
 pub struct GephiClient {
     base_url: String,
@@ -19,26 +21,27 @@ impl GephiClient {
         node_id: &str,
         label: &str,
         depth: usize,
+        style: &NodeStyle,
     ) -> Result<String, reqwest::Error> {
-        let body = json!({
-            "an": {
-                node_id: {
-                    "label": label,
-                    "depth": depth
-                }
-            }
-        });
-
-        let res = self
-            .client
-            .post(&format!("{}?operation=updateGraph", self.base_url))
-            .json(&body)
-            .send()
-            .await?
-            .text()
-            .await?;
+        self.send(&GraphEvent::AddNode {
+            id: node_id.to_string(),
+            label: label.to_string(),
+            depth,
+            style: *style,
+        })
+        .await
+    }
 
-        Ok(res)
+    pub async fn change_node(
+        &self,
+        node_id: &str,
+        style: &NodeStyle,
+    ) -> Result<String, reqwest::Error> {
+        self.send(&GraphEvent::ChangeNode {
+            id: node_id.to_string(),
+            style: *style,
+        })
+        .await
     }
 
     pub async fn add_edge(
@@ -47,21 +50,23 @@ impl GephiClient {
         source: &str,
         target: &str,
         directed: bool,
+        style: &EdgeStyle,
     ) -> Result<String, reqwest::Error> {
-        let body = json!({
-            "ae": {
-                edge_id: {
-                    "source": source,
-                    "target": target,
-                    "directed": directed
-                }
-            }
-        });
+        self.send(&GraphEvent::AddEdge {
+            id: edge_id.to_string(),
+            source: source.to_string(),
+            target: target.to_string(),
+            directed,
+            style: *style,
+        })
+        .await
+    }
 
+    pub async fn send(&self, event: &GraphEvent) -> Result<String, reqwest::Error> {
         let res = self
             .client
-            .post(&format!("{}?operation=updateGraph", self.base_url))
-            .json(&body)
+            .post(format!("{}?operation=updateGraph", self.base_url))
+            .json(&event.to_json())
             .send()
             .await?
             .text()
@@ -0,0 +1,157 @@
+use crate::http::{read_request, write_response};
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+/// The graph built from the operations received by a [`MockGephi`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MockGraph {
+    pub nodes: BTreeMap<String, Map<String, Value>>,
+    pub edges: BTreeMap<String, Map<String, Value>>,
+}
+
+impl MockGraph {
+    pub fn node(&self, id: &str) -> Option<&Map<String, Value>> {
+        self.nodes.get(id)
+    }
+
+    pub fn has_node(&self, id: &str) -> bool {
+        self.nodes.contains_key(id)
+    }
+
+    pub fn has_edge(&self, source: &str, target: &str) -> bool {
+        self.edges.values().any(|edge| {
+            edge.get("source").and_then(Value::as_str) == Some(source)
+                && edge.get("target").and_then(Value::as_str) == Some(target)
+        })
+    }
+
+    fn apply(&mut self, operation: &Value) {
+        let Some(operation) = operation.as_object() else {
+            return;
+        };
+
+        for (kind, items) in operation {
+            let Some(items) = items.as_object() else {
+                continue;
+            };
+            for (id, attributes) in items {
+                let attributes = attributes.as_object().cloned().unwrap_or_default();
+                match kind.as_str() {
+                    "an" => merge(self.nodes.entry(id.clone()).or_default(), attributes),
+                    "cn" => {
+                        if let Some(node) = self.nodes.get_mut(id) {
+                            merge(node, attributes);
+                        }
+                    }
+                    "dn" => {
+                        self.nodes.remove(id);
+                        self.edges.retain(|_, edge| {
+                            edge.get("source").and_then(Value::as_str) != Some(id)
+                                && edge.get("target").and_then(Value::as_str) != Some(id)
+                        });
+                    }
+                    "ae" => merge(self.edges.entry(id.clone()).or_default(), attributes),
+                    "ce" => {
+                        if let Some(edge) = self.edges.get_mut(id) {
+                            merge(edge, attributes);
+                        }
+                    }
+                    "de" => {
+                        self.edges.remove(id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn merge(target: &mut Map<String, Value>, attributes: Map<String, Value>) {
+    target.extend(attributes);
+}
+
+#[derive(Default)]
+struct MockState {
+    operations: Vec<Value>,
+    graph: MockGraph,
+}
+
+/// An in-process stand-in for the Gephi graph streaming endpoint.
+///
+/// It accepts `updateGraph` requests on a local port, records every operation
+/// and applies it to a [`MockGraph`], so streaming can be checked offline.
+pub struct MockGephi {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockGephi {
+    pub fn start() -> Result<Self> {
+        let listener =
+            TcpListener::bind("127.0.0.1:0").context("Failed to bind mock Gephi server")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let accept_state = state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let state = accept_state.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &state) {
+                        error!("Mock Gephi connection failed: {:?}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(MockGephi { addr, state })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}/workspace1", self.addr)
+    }
+
+    pub fn operations(&self) -> Vec<Value> {
+        self.state.lock().unwrap().operations.clone()
+    }
+
+    pub fn graph(&self) -> MockGraph {
+        self.state.lock().unwrap().graph.clone()
+    }
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<MockState>) -> Result<()> {
+    let request = read_request(&stream)?;
+    if request.method != "POST" || !request.path.contains("operation=updateGraph") {
+        write_response(&stream, "404 Not Found", "text/plain", b"Not found")?;
+        return Ok(());
+    }
+
+    let operation: Value = match serde_json::from_slice(&request.body) {
+        Ok(operation) => operation,
+        Err(e) => {
+            write_response(
+                &stream,
+                "400 Bad Request",
+                "text/plain",
+                e.to_string().as_bytes(),
+            )?;
+            return Ok(());
+        }
+    };
+
+    {
+        let mut state = state.lock().unwrap();
+        state.graph.apply(&operation);
+        state.operations.push(operation);
+    }
+    write_response(&stream, "200 OK", "text/plain", b"")?;
+
+    Ok(())
+}
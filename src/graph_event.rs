@@ -0,0 +1,66 @@
+use crate::style::{EdgeStyle, NodeStyle};
+
+use serde_json::{Map, Value, json};
+
+/// A graph update in the Gephi streaming format, shared by every live graph sink.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphEvent {
+    AddNode {
+        id: String,
+        label: String,
+        depth: usize,
+        style: NodeStyle,
+    },
+    ChangeNode {
+        id: String,
+        style: NodeStyle,
+    },
+    AddEdge {
+        id: String,
+        source: String,
+        target: String,
+        directed: bool,
+        style: EdgeStyle,
+    },
+}
+
+impl GraphEvent {
+    pub fn to_json(&self) -> Value {
+        match self {
+            GraphEvent::AddNode {
+                id,
+                label,
+                depth,
+                style,
+            } => {
+                let mut attributes = Map::new();
+                attributes.insert("label".to_string(), json!(label));
+                attributes.insert("depth".to_string(), json!(depth));
+                style.apply(&mut attributes);
+
+                json!({ "an": { id: attributes } })
+            }
+            GraphEvent::ChangeNode { id, style } => {
+                let mut attributes = Map::new();
+                style.apply(&mut attributes);
+
+                json!({ "cn": { id: attributes } })
+            }
+            GraphEvent::AddEdge {
+                id,
+                source,
+                target,
+                directed,
+                style,
+            } => {
+                let mut attributes = Map::new();
+                attributes.insert("source".to_string(), json!(source));
+                attributes.insert("target".to_string(), json!(target));
+                attributes.insert("directed".to_string(), json!(directed));
+                style.apply(&mut attributes);
+
+                json!({ "ae": { id: attributes } })
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use dashmap::{DashMap, mapref::entry::Entry};
+use std::sync::{Arc, RwLock};
+
+/// A URL interned in a [`UrlTable`].
+pub type NodeId = u32;
+
+/// Gives every URL a numeric id, so pages and edges hold 4-byte ids
+/// instead of their own copies of the URL text.
+///
+/// The text of each URL is allocated once, shared by the lookup map and the table.
+#[derive(Debug, Default)]
+pub struct UrlTable {
+    ids: DashMap<Arc<str>, NodeId>,
+    urls: RwLock<Vec<Arc<str>>>,
+}
+
+impl UrlTable {
+    pub fn new() -> Self {
+        UrlTable::default()
+    }
+
+    /// The id of a URL, adding it to the table if it is new.
+    pub fn intern(&self, url: &str) -> NodeId {
+        if let Some(id) = self.ids.get(url) {
+            return *id;
+        }
+
+        match self.ids.entry(Arc::from(url)) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let mut urls = self.urls.write().unwrap();
+                let id = NodeId::try_from(urls.len()).expect("Too many URLs to intern");
+                urls.push(entry.key().clone());
+                entry.insert(id);
+                id
+            }
+        }
+    }
+
+    /// The id of a URL already in the table.
+    pub fn get(&self, url: &str) -> Option<NodeId> {
+        self.ids.get(url).map(|id| *id)
+    }
+
+    pub fn resolve(&self, id: NodeId) -> Arc<str> {
+        self.urls.read().unwrap()[id as usize].clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.urls.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interns_each_url_once() {
+        let table = UrlTable::new();
+        assert!(table.is_empty());
+
+        let a = table.intern("https://a.test/");
+        let b = table.intern("https://a.test/b");
+        assert_eq!(table.intern("https://a.test/"), a);
+        assert_ne!(a, b);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get("https://a.test/b"), Some(b));
+        assert_eq!(table.get("https://a.test/c"), None);
+        assert_eq!(&*table.resolve(b), "https://a.test/b");
+    }
+}
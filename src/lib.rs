@@ -1,11 +1,62 @@
+mod a11y;
 mod args;
+mod budget;
 mod crawler;
 mod exporter;
+mod filter;
+mod frontier;
 mod gephi;
+mod gephi_mock;
+mod graph_event;
+mod http;
+mod intern;
+mod live;
+mod metadata;
+mod mirror;
+mod page_graph;
+mod robots;
+mod scope;
+mod security;
+mod sink;
+mod sitemap;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod store;
+mod style;
+mod text;
 mod url_data;
+mod warc;
 
-pub use args::Args;
+pub use a11y::{A11yFinding, A11yRule, lint};
+pub use args::{Args, Command};
+pub use budget::{Budget, BudgetKind};
 pub use crawler::{Crawler, CrawlerData};
-pub use exporter::Exporter;
+pub use exporter::{
+    A11yPage, A11yReport, A11yRuleSummary, AuditCheck, AuditIssue, AuditSettings, CanonicalIssue,
+    CanonicalProblem, Collapse, Column, CrawlMetadata, DuplicateGroup, Exporter, Format,
+    HostSecurity, NearDuplicateCluster, Severity,
+};
+pub use filter::{Action, Filter, Pattern, Rule};
+pub use frontier::{
+    Candidate, Frontier, InLinks, Order, Queued, Score, Scorer, SitemapPriority, UrlDepth,
+};
 pub use gephi::GephiClient;
-pub use url_data::Url;
+pub use gephi_mock::{MockGephi, MockGraph};
+pub use graph_event::GraphEvent;
+pub use intern::{NodeId, UrlTable};
+pub use live::LiveServer;
+pub use metadata::{Alternate, PageMetadata};
+pub use mirror::Mirror;
+pub use page_graph::PageGraph;
+pub use robots::{Directives, RobotsPolicy};
+pub use scope::{Scope, ScopeMode};
+pub use security::{SecurityFinding, SecurityIssue, check_document, check_headers};
+pub use sink::{CrawlSink, Exchange, Fetch, JsonlSink};
+pub use sitemap::{SitemapEntry, fetch_sitemap, fetch_sitemap_entries};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSink;
+pub use store::{BodyStore, body_hash};
+pub use style::{ColorRule, EdgeColor, EdgeStyle, NodeColor, NodeSize, NodeStyle, Rgb, Styler};
+pub use text::{simhash, similarity, visible_text};
+pub use url_data::{Edge, Link, Skip, Url};
+pub use warc::WarcWriter;
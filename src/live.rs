@@ -0,0 +1,233 @@
+use crate::GraphEvent;
+use crate::http::{read_request, write_response};
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value, json};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use tracing::{error, info};
+
+const PAGE: &str = include_str!("live.html");
+
+/// How many of the latest events are kept for clients following along.
+const MAX_RECENT: usize = 10_000;
+
+#[derive(Default)]
+struct EventLog {
+    state: Mutex<LogState>,
+    updated: Condvar,
+}
+
+/// The graph as the events left it, sent to new clients, and the latest events,
+/// sent to clients already following. A client falling further behind than the
+/// latest events gets the whole graph again, which the page applies idempotently.
+#[derive(Default)]
+struct LogState {
+    nodes: Vec<(String, Map<String, Value>)>,
+    node_index: HashMap<String, usize>,
+    edges: Vec<(String, Map<String, Value>)>,
+    edge_index: HashMap<String, usize>,
+    recent: VecDeque<String>,
+    /// The number of the first event in `recent`.
+    first: usize,
+}
+
+impl LogState {
+    fn push(&mut self, event: &GraphEvent) {
+        let json = event.to_json();
+        if let Value::Object(operations) = &json {
+            for (operation, items) in operations {
+                let Value::Object(items) = items else {
+                    continue;
+                };
+                let (list, index) = match operation.as_str() {
+                    "ae" => (&mut self.edges, &mut self.edge_index),
+                    _ => (&mut self.nodes, &mut self.node_index),
+                };
+                for (id, attributes) in items {
+                    let Value::Object(attributes) = attributes else {
+                        continue;
+                    };
+                    match index.get(id) {
+                        Some(&position) => list[position].1.extend(attributes.clone()),
+                        None => {
+                            index.insert(id.clone(), list.len());
+                            list.push((id.clone(), attributes.clone()));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.recent.push_back(json.to_string());
+        if self.recent.len() > MAX_RECENT {
+            self.recent.pop_front();
+            self.first += 1;
+        }
+    }
+
+    /// The number of the next event.
+    fn next(&self) -> usize {
+        self.first + self.recent.len()
+    }
+
+    /// Events adding every node, then every edge, as they are now.
+    fn snapshot(&self) -> Vec<String> {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(id, attributes)| json!({ "an": { id: attributes } }).to_string());
+        let edges = self
+            .edges
+            .iter()
+            .map(|(id, attributes)| json!({ "ae": { id: attributes } }).to_string());
+
+        nodes.chain(edges).collect()
+    }
+}
+
+/// Serves the crawl graph to browsers: a bundled page at `/` and the graph
+/// events, in the Gephi streaming format, as Server-Sent Events at `/events`.
+pub struct LiveServer {
+    addr: SocketAddr,
+    log: Arc<EventLog>,
+}
+
+impl LiveServer {
+    pub fn start(addr: &str) -> Result<Self> {
+        let listener =
+            TcpListener::bind(addr).context(format!("Failed to bind live server to {}", addr))?;
+        let addr = listener.local_addr()?;
+        let log = Arc::new(EventLog::default());
+
+        let accept_log = log.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let log = accept_log.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &log) {
+                        error!("Live server connection failed: {:?}", e);
+                    }
+                });
+            }
+        });
+        info!("Live graph served on http://{}", addr);
+
+        Ok(LiveServer { addr, log })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn publish(&self, event: &GraphEvent) {
+        self.log.state.lock().unwrap().push(event);
+        self.log.updated.notify_all();
+    }
+
+    /// Keeps the process serving the final graph until it is interrupted.
+    pub fn wait(&self) -> ! {
+        loop {
+            std::thread::park();
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, log: &EventLog) -> Result<()> {
+    let request = read_request(&stream)?;
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => write_response(&stream, "200 OK", "text/html", PAGE.as_bytes())?,
+        ("GET", "/events") => stream_events(stream, log)?,
+        _ => write_response(&stream, "404 Not Found", "text/plain", b"Not found")?,
+    }
+
+    Ok(())
+}
+
+fn stream_events(mut stream: TcpStream, log: &EventLog) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n"
+    )?;
+
+    let (mut pending, mut sent) = {
+        let state = log.state.lock().unwrap();
+        (state.snapshot(), state.next())
+    };
+    loop {
+        for event in pending {
+            write!(stream, "data: {}\n\n", event)?;
+        }
+        stream.flush()?;
+
+        let mut state = log.state.lock().unwrap();
+        while state.next() == sent {
+            state = log.updated.wait(state).unwrap();
+        }
+        pending = match sent < state.first {
+            true => state.snapshot(),
+            false => state.recent.range(sent - state.first..).cloned().collect(),
+        };
+        sent = state.next();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EdgeStyle, NodeStyle};
+
+    fn node(id: &str, size: f32) -> GraphEvent {
+        GraphEvent::AddNode {
+            id: id.to_string(),
+            label: id.to_string(),
+            depth: 0,
+            style: NodeStyle {
+                color: None,
+                size: Some(size),
+            },
+        }
+    }
+
+    #[test]
+    fn snapshot_merges_node_changes() {
+        let mut state = LogState::default();
+        state.push(&node("a", 10.0));
+        state.push(&GraphEvent::ChangeNode {
+            id: "a".to_string(),
+            style: NodeStyle {
+                color: None,
+                size: Some(20.0),
+            },
+        });
+        state.push(&GraphEvent::AddEdge {
+            id: "a-b".to_string(),
+            source: "a".to_string(),
+            target: "b".to_string(),
+            directed: true,
+            style: EdgeStyle::default(),
+        });
+
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        let first: Value = serde_json::from_str(&snapshot[0]).unwrap();
+        assert_eq!(first["an"]["a"]["size"], json!(20.0));
+        assert_eq!(first["an"]["a"]["label"], json!("a"));
+        assert!(snapshot[1].contains("\"ae\""));
+    }
+
+    #[test]
+    fn recent_events_are_bounded() {
+        let mut state = LogState::default();
+        for index in 0..MAX_RECENT + 5 {
+            state.push(&node(&index.to_string(), 1.0));
+        }
+
+        assert_eq!(state.recent.len(), MAX_RECENT);
+        assert_eq!(state.first, 5);
+        assert_eq!(state.next(), MAX_RECENT + 5);
+    }
+}
@@ -1,31 +1,139 @@
-use ferris_the_crawler::{Args, Crawler};
+use ferris_the_crawler::{
+    Args, AuditSettings, BodyStore, Command, Crawler, Format, JsonlSink, LiveServer, MockGephi,
+    Order, Score, SitemapPriority, Skip, WarcWriter, fetch_sitemap, fetch_sitemap_entries,
+};
 
 use anyhow::Result;
-use clap::Parser;
-use tracing::info;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
 
 fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
     let args = Args::parse();
+    if args.command.is_none() && args.explain.is_none() {
+        Args::command()
+            .error(
+                ErrorKind::MissingSubcommand,
+                "a subcommand is required unless --explain is given",
+            )
+            .exit();
+    }
     info!("Starting crawler with args: {:?}", args);
 
     #[cfg(feature = "flamegraph")]
     let guard = pprof::ProfilerGuard::new(100).unwrap();
 
-    let crawler = Crawler::new(args);
+    let live_server = match &args.serve {
+        Some(addr) => Some(Arc::new(LiveServer::start(addr)?)),
+        None => None,
+    };
+
+    let gephi_mock = match args.gephi_dry_run {
+        true => Some(MockGephi::start()?),
+        false => None,
+    };
+
+    let mut crawler = Crawler::new(args);
+    if let Some(url) = &crawler.args().explain {
+        println!("{}", crawler.explain(url));
+        return Ok(());
+    }
+
+    if crawler.args().order == Order::BestFirst && crawler.args().score == Score::Sitemap {
+        let sitemap = url::Url::parse(&crawler.args().url)?
+            .join("/sitemap.xml")?
+            .to_string();
+        let entries = fetch_sitemap_entries(&sitemap).unwrap_or_else(|e| {
+            warn!("No sitemap priorities, no sitemap at {}: {:?}", sitemap, e);
+            Vec::new()
+        });
+        let scorer =
+            SitemapPriority::new(entries.into_iter().map(|entry| (entry.url, entry.priority)));
+        crawler = crawler.with_scorer(Arc::new(scorer));
+    }
+
+    if let Some(mock) = &gephi_mock {
+        crawler = crawler.with_gephi_mock(mock);
+    }
+    if let Some(server) = &live_server {
+        crawler = crawler.with_live_server(server.clone());
+    }
+
+    if let Some(dir) = &crawler.args().store {
+        let store = Arc::new(BodyStore::open(dir)?);
+        crawler = crawler.with_store(store);
+    }
+
+    if let Some(prefix) = &crawler.args().warc {
+        let warc = Arc::new(WarcWriter::create(prefix, crawler.args().warc_max_size)?);
+        crawler = crawler.with_sink(warc);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &crawler.args().sqlite {
+        let store = Arc::new(ferris_the_crawler::SqliteSink::open(path)?);
+        let (pages, edges) = store.load()?;
+        crawler = crawler.resume(pages, edges).with_sink(store);
+    }
+
+    // JSON Lines are streamed while crawling instead of being exported at the end.
+    let streamed = crawler.args().format == Format::Jsonl;
+    if let (true, Some(output)) = (streamed, &crawler.args().output) {
+        let sink = Arc::new(
+            JsonlSink::create(output)?
+                .with_indexes_noindex(crawler.args().robots.indexes_noindex()),
+        );
+        crawler = crawler.with_sink(sink);
+    }
 
     crawler.crawl()?;
     info!(
         "Crawling completed successfully with {} urls",
-        crawler.urls().len()
+        crawler.data.graph.page_count()
     );
+    if let Some(budget) = crawler.data.budget.exhausted() {
+        warn!("Crawl stopped early: the {} budget ran out", budget);
+    }
+    for host in crawler.data.budget.exhausted_hosts() {
+        info!("Page budget of host {} ran out", host);
+    }
+    let (by_type, too_large) = crawler
+        .iter_urls()
+        .fold((0, 0), |(by_type, too_large), url| match url.skipped {
+            Some(Skip::ContentType) => (by_type + 1, too_large),
+            Some(Skip::TooLarge) => (by_type, too_large + 1),
+            None => (by_type, too_large),
+        });
+    if by_type + too_large > 0 {
+        info!(
+            "Skipped {} pages by content type and {} over the maximum body size",
+            by_type, too_large
+        );
+    }
+    if !crawler.data.filter.is_empty() {
+        let (counts, unmatched) = crawler.data.filter.counts();
+        for (rule, count) in counts {
+            info!("Filter rule {} matched {} urls", rule, count);
+        }
+        info!("No filter rule matched {} urls", unmatched);
+    }
 
-    if let Some(output) = &crawler.args().output {
-        crawler.exporter().to_file(output)?;
-        info!("Data exported to file: {}", output);
+    if let Some(mock) = &gephi_mock {
+        let graph = mock.graph();
+        info!(
+            "Gephi dry run received {} operations ({} nodes, {} edges)",
+            mock.operations().len(),
+            graph.nodes.len(),
+            graph.edges.len()
+        );
     }
 
+    write_reports(&crawler, streamed)?;
+
     #[cfg(feature = "flamegraph")]
     {
         if let Ok(report) = guard.report().build() {
@@ -34,5 +142,106 @@ fn main() -> Result<()> {
         }
     }
 
+    if let Some(server) = live_server {
+        info!(
+            "Still serving the live graph on http://{}, press Ctrl-C to exit",
+            server.addr()
+        );
+        server.wait();
+    }
+
+    Ok(())
+}
+
+/// Writes the export and the reports asked for once the crawl is over.
+fn write_reports(crawler: &Crawler, streamed: bool) -> Result<()> {
+    let args = crawler.args();
+    let audited = args.canonical_audit.is_some() || matches!(args.mode(), Command::Audit { .. });
+    let exported = (args.output.is_some() && !streamed)
+        || args.duplicates.is_some()
+        || args.near_duplicates.is_some()
+        || args.a11y.is_some()
+        || args.security.is_some();
+    if !audited && !exported {
+        if let Some(output) = &args.output {
+            info!("Data exported to file: {}", output);
+        }
+        return Ok(());
+    }
+
+    // The crawl is copied out of the graph once: the audits look at it as it is,
+    // then it is filtered and merged for the exports.
+    let exporter = crawler.crawl_exporter();
+
+    if let Some(path) = &args.canonical_audit {
+        std::fs::write(path, exporter.canonical_audit_json()?)?;
+        info!("Canonical audit written to file: {}", path);
+    }
+
+    if let Command::Audit {
+        dir,
+        sitemap,
+        max_title_length,
+        thin_content,
+    } = args.mode()
+    {
+        let sitemap = match sitemap {
+            Some(sitemap) => sitemap.clone(),
+            None => url::Url::parse(&args.url)?
+                .join("/sitemap.xml")?
+                .to_string(),
+        };
+        let settings = AuditSettings {
+            max_title_length: *max_title_length,
+            thin_content: *thin_content,
+            sitemap: fetch_sitemap(&sitemap).unwrap_or_else(|e| {
+                warn!(
+                    "Not checking orphan pages, no sitemap at {}: {:?}",
+                    sitemap, e
+                );
+                Vec::new()
+            }),
+        };
+
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(
+            Path::new(dir).join("audit.json"),
+            exporter.audit_json(&settings)?,
+        )?;
+        std::fs::write(
+            Path::new(dir).join("audit.html"),
+            exporter.audit_html(&settings),
+        )?;
+        info!("Audit report written to directory: {}", dir);
+    }
+
+    let exporter = crawler.export_view(exporter);
+    if let Some(output) = &args.output {
+        if !streamed {
+            exporter.to_file(args.format, output)?;
+        }
+        info!("Data exported to file: {}", output);
+    }
+
+    if let Some(path) = &args.duplicates {
+        std::fs::write(path, exporter.duplicates_json()?)?;
+        info!("Duplicates report written to file: {}", path);
+    }
+
+    if let Some(path) = &args.near_duplicates {
+        std::fs::write(path, exporter.near_duplicates_json(args.similarity)?)?;
+        info!("Near-duplicates report written to file: {}", path);
+    }
+
+    if let Some(path) = &args.a11y {
+        std::fs::write(path, exporter.a11y_json()?)?;
+        info!("Accessibility report written to file: {}", path);
+    }
+
+    if let Some(path) = &args.security {
+        std::fs::write(path, exporter.security_json()?)?;
+        info!("Security report written to file: {}", path);
+    }
+
     Ok(())
 }
@@ -0,0 +1,142 @@
+use crate::text::visible_text;
+
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+/// An alternate version of a page in another language, from `<link rel="alternate" hreflang>`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Alternate {
+    pub hreflang: String,
+    pub url: String,
+}
+
+/// What a page says about itself in its markup.
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub robots: Option<String>,
+    pub canonical: Option<String>,
+    pub hreflang: Vec<Alternate>,
+    pub h1: Vec<String>,
+    pub h2: Vec<String>,
+    pub lang: Option<String>,
+    pub word_count: Option<usize>,
+}
+
+impl PageMetadata {
+    /// Reads the metadata of a page, resolving canonical and alternate links against its URL.
+    pub fn extract(document: &Html, page_url: &str) -> Self {
+        let base = url::Url::parse(page_url).ok();
+        let resolve = |href: &str| match &base {
+            Some(base) => base
+                .join(href.trim())
+                .map(|url| url.to_string())
+                .unwrap_or_else(|_| href.to_string()),
+            None => href.to_string(),
+        };
+
+        let hreflang = Selector::parse("link[rel~=alternate][hreflang][href]").unwrap();
+        let canonical = attribute(document, "link[rel~=canonical][href]", "href");
+
+        PageMetadata {
+            title: text(document, "title").into_iter().next(),
+            description: attribute(document, "meta[name=description i][content]", "content"),
+            robots: attribute(document, "meta[name=robots i][content]", "content"),
+            canonical: canonical.as_deref().map(resolve),
+            hreflang: document
+                .select(&hreflang)
+                .filter_map(|element| {
+                    Some(Alternate {
+                        hreflang: element.value().attr("hreflang")?.trim().to_string(),
+                        url: resolve(element.value().attr("href")?),
+                    })
+                })
+                .collect(),
+            h1: text(document, "h1"),
+            h2: text(document, "h2"),
+            lang: attribute(document, "html[lang]", "lang"),
+            word_count: Some(visible_text(document).split_whitespace().count()),
+        }
+    }
+}
+
+/// The trimmed value of an attribute on the first matching element, if not empty.
+fn attribute(document: &Html, selector: &str, name: &str) -> Option<String> {
+    let selector = Selector::parse(selector).unwrap();
+
+    document
+        .select(&selector)
+        .filter_map(|element| element.value().attr(name))
+        .map(|value| value.trim().to_string())
+        .find(|value| !value.is_empty())
+}
+
+/// The text of every matching element, with whitespace collapsed and empty ones left out.
+fn text(document: &Html, selector: &str) -> Vec<String> {
+    let selector = Selector::parse(selector).unwrap();
+
+    document
+        .select(&selector)
+        .map(|element| element.text().collect::<Vec<_>>().join(" "))
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_head_and_headings() {
+        let document = Html::parse_document(
+            r#"<html lang="en"><head>
+                <title>  A   page </title>
+                <meta name="Description" content=" About things ">
+                <meta name="robots" content="noindex, follow">
+                <link rel="canonical" href="/canonical ">
+                <link rel="alternate" hreflang="fr" href="https://a.test/fr/">
+                <link rel="alternate" href="https://a.test/feed.xml">
+            </head><body>
+                <h1>Main <em>title</em></h1><h1> </h1>
+                <h2>One</h2><h2>Two</h2>
+                <p>Four words of text</p>
+            </body></html>"#,
+        );
+
+        let metadata = PageMetadata::extract(&document, "https://a.test/dir/page");
+        assert_eq!(metadata.title.as_deref(), Some("A page"));
+        assert_eq!(metadata.description.as_deref(), Some("About things"));
+        assert_eq!(metadata.robots.as_deref(), Some("noindex, follow"));
+        assert_eq!(
+            metadata.canonical.as_deref(),
+            Some("https://a.test/canonical")
+        );
+        assert_eq!(
+            metadata.hreflang,
+            vec![Alternate {
+                hreflang: "fr".to_string(),
+                url: "https://a.test/fr/".to_string(),
+            }]
+        );
+        assert_eq!(metadata.h1, vec!["Main title"]);
+        assert_eq!(metadata.h2, vec!["One", "Two"]);
+        assert_eq!(metadata.lang.as_deref(), Some("en"));
+        assert_eq!(metadata.word_count, Some(8));
+    }
+
+    #[test]
+    fn missing_and_empty_values_are_none() {
+        let document = Html::parse_document(
+            r#"<html><head><meta name="description" content="  "></head><body></body></html>"#,
+        );
+
+        let metadata = PageMetadata::extract(&document, "not a url");
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.canonical, None);
+        assert!(metadata.h1.is_empty());
+        assert_eq!(metadata.word_count, Some(0));
+    }
+}
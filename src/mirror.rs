@@ -0,0 +1,254 @@
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use regex::{Captures, Regex};
+use std::path::{Component, Path, PathBuf};
+use tracing::{error, info};
+
+struct SavedFile {
+    path: PathBuf,
+    html: bool,
+}
+
+/// Saves fetched pages and assets into a directory tree mirroring their URLs.
+///
+/// Links in saved pages are only rewritten once the crawl is over, when it is
+/// known which of their targets ended up in the mirror.
+pub struct Mirror {
+    root: PathBuf,
+    saved: DashMap<String, SavedFile>,
+    claimed: DashMap<String, ()>,
+}
+
+impl Mirror {
+    pub fn new(root: &str) -> Self {
+        Mirror {
+            root: PathBuf::from(root),
+            saved: DashMap::new(),
+            claimed: DashMap::new(),
+        }
+    }
+
+    /// Returns true the first time an asset is seen, so it is only fetched once.
+    pub fn claim(&self, url: &str) -> bool {
+        self.claimed.insert(url.to_string(), ()).is_none()
+    }
+
+    pub fn save(&self, url: &str, content_type: Option<&str>, body: &[u8]) -> Result<()> {
+        let html = content_type.is_some_and(|content_type| content_type.starts_with("text/html"));
+        let Some(path) = local_path(url, html) else {
+            return Ok(());
+        };
+
+        let file_path = self.root.join(&path);
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)
+                .context(format!("Failed to create directory: {}", parent.display()))?;
+        }
+        std::fs::write(&file_path, body)
+            .context(format!("Failed to write file: {}", file_path.display()))?;
+        info!("Saved {} to {}", url, file_path.display());
+
+        self.saved.insert(url.to_string(), SavedFile { path, html });
+
+        Ok(())
+    }
+
+    /// Points links between saved files at their local copies.
+    ///
+    /// Links are resolved against the `<base>` of a page if it has one, which is
+    /// then removed as the local links are relative to the saved file. Links to
+    /// what was not mirrored are made absolute, so they still work offline.
+    pub fn rewrite_links(&self) -> Result<()> {
+        let attribute = Regex::new(r#"(?i)(\s(?:href|src)\s*=\s*)("[^"]*"|'[^']*'|[^\s"'>]+)"#)?;
+        let base_tag = Regex::new(r#"(?is)<base\s[^>]*>"#)?;
+
+        for entry in self.saved.iter().filter(|entry| entry.value().html) {
+            let (url, saved) = (entry.key(), entry.value());
+            let file_path = self.root.join(&saved.path);
+            let html = std::fs::read(&file_path)
+                .context(format!("Failed to read file: {}", file_path.display()))?;
+            let html = String::from_utf8_lossy(&html);
+            let Ok(page_url) = url::Url::parse(url) else {
+                continue;
+            };
+            let base_href = base_tag
+                .find(&html)
+                .and_then(|tag| attribute.captures(tag.as_str()))
+                .map(|captures| attribute_value(&captures[2]));
+            let (base, html) = match base_href.and_then(|href| page_url.join(href.trim()).ok()) {
+                Some(base) => (base, base_tag.replace_all(&html, "")),
+                None => (page_url, html),
+            };
+
+            let rewritten = attribute.replace_all(&html, |captures: &Captures| {
+                let value = attribute_value(&captures[2]);
+                let link = self
+                    .local_link(&base, &saved.path, &value)
+                    .or_else(|| Some(base.join(value.trim()).ok()?.to_string()));
+                match link {
+                    Some(link) => format!("{}\"{}\"", &captures[1], link.replace('&', "&amp;")),
+                    None => captures[0].to_string(),
+                }
+            });
+
+            if let Err(e) = std::fs::write(&file_path, rewritten.as_bytes()) {
+                error!(
+                    "Failed to rewrite links in {}: {:?}",
+                    file_path.display(),
+                    e
+                );
+            }
+        }
+        info!(
+            "Mirrored {} files into {}",
+            self.saved.len(),
+            self.root.display()
+        );
+
+        Ok(())
+    }
+
+    /// The link from one saved file to the local copy of `href`, if there is one.
+    fn local_link(&self, base: &url::Url, from: &Path, href: &str) -> Option<String> {
+        let mut target = base.join(href.trim()).ok()?;
+        let fragment = target.fragment().map(String::from);
+        target.set_fragment(None);
+
+        let saved = self.saved.get(target.as_str())?;
+        let mut link = relative_path(from.parent()?, &saved.path)
+            .replace('%', "%25")
+            .replace('?', "%3F")
+            .replace('#', "%23");
+        if let Some(fragment) = fragment {
+            link.push('#');
+            link.push_str(&fragment);
+        }
+
+        Some(link)
+    }
+}
+
+/// An attribute value without its quotes and with `&amp;` decoded.
+fn attribute_value(value: &str) -> String {
+    value
+        .trim_matches(|c| c == '"' || c == '\'')
+        .replace("&amp;", "&")
+}
+
+/// Where a URL is stored in the mirror, relative to its root.
+///
+/// Directories get an `index.html`, query strings are appended after an `@`,
+/// and HTML pages always end in `.html` so they open in a browser.
+fn local_path(url: &str, html: bool) -> Option<PathBuf> {
+    let parsed = url::Url::parse(url).ok()?;
+    let mut path = PathBuf::from(match parsed.port() {
+        Some(port) => format!("{}:{}", parsed.host_str()?, port),
+        None => parsed.host_str()?.to_string(),
+    });
+
+    let segments = parsed.path_segments()?.collect::<Vec<_>>();
+    let (directories, file_name) = segments.split_at(segments.len().saturating_sub(1));
+    for directory in directories.iter().filter(|directory| !directory.is_empty()) {
+        path.push(directory);
+    }
+
+    let mut file_name = match file_name.first() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => "index.html".to_string(),
+    };
+    if let Some(query) = parsed.query() {
+        file_name.push('@');
+        file_name.push_str(&query.replace('/', "%2F"));
+    }
+    if html && !file_name.ends_with(".html") && !file_name.ends_with(".htm") {
+        file_name.push_str(".html");
+    }
+    path.push(file_name);
+
+    Some(path)
+}
+
+fn relative_path(from: &Path, to: &Path) -> String {
+    let from = from.components().collect::<Vec<Component>>();
+    let to = to.components().collect::<Vec<Component>>();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut parts = vec![".."; from.len() - common];
+    parts.extend(
+        to[common..]
+            .iter()
+            .filter_map(|component| component.as_os_str().to_str()),
+    );
+
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_resolve_against_the_base() {
+        let root = std::env::temp_dir().join(format!("mirror-{}", uuid::Uuid::new_v4()));
+        let mirror = Mirror::new(root.to_str().unwrap());
+        mirror
+            .save(
+                "https://a.test/page",
+                Some("text/html"),
+                br#"<base href="/docs/"><a href="guide">Guide</a> <a href="other">Other</a>"#,
+            )
+            .unwrap();
+        mirror
+            .save("https://a.test/docs/guide", Some("text/html"), b"")
+            .unwrap();
+
+        mirror.rewrite_links().unwrap();
+        let page = std::fs::read_to_string(root.join("a.test/page.html")).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(!page.contains("<base"));
+        assert!(page.contains(r#"href="docs/guide.html""#));
+        assert!(page.contains(r#"href="https://a.test/docs/other""#));
+    }
+
+    #[test]
+    fn links_to_unsaved_pages_become_absolute() {
+        let root = std::env::temp_dir().join(format!("mirror-{}", uuid::Uuid::new_v4()));
+        let mirror = Mirror::new(root.to_str().unwrap());
+        mirror
+            .save(
+                "https://a.test/docs/page",
+                Some("text/html"),
+                br##"<a href="guide#intro">Guide</a> <a href='../missing?a=1&amp;b=2'>Missing</a> <a href="#top">Top</a> <img src=/logo.png>"##,
+            )
+            .unwrap();
+        mirror
+            .save("https://a.test/docs/guide", Some("text/html"), b"")
+            .unwrap();
+
+        mirror.rewrite_links().unwrap();
+        let page = std::fs::read_to_string(root.join("a.test/docs/page.html")).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(page.contains(r#"href="guide.html#intro""#));
+        assert!(page.contains(r#"href="https://a.test/missing?a=1&amp;b=2""#));
+        assert!(page.contains(r#"href="page.html#top""#));
+        assert!(page.contains(r#"src="https://a.test/logo.png""#));
+    }
+
+    #[test]
+    fn local_paths_keep_queries_and_html_extensions() {
+        assert_eq!(
+            local_path("https://a.test/", true),
+            Some(PathBuf::from("a.test/index.html"))
+        );
+        assert_eq!(
+            local_path("https://a.test:8080/a/b?x=1/2", true),
+            Some(PathBuf::from("a.test:8080/a/b@x=1%2F2.html"))
+        );
+        assert_eq!(
+            local_path("https://a.test/style.css", false),
+            Some(PathBuf::from("a.test/style.css"))
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use crate::a11y::A11yFinding;
+use crate::intern::{NodeId, UrlTable};
+use crate::metadata::PageMetadata;
+use crate::robots::NOFOLLOW_RELS;
+use crate::security::SecurityFinding;
+use crate::url_data::{Edge, Skip, Url};
+
+use dashmap::{DashMap, mapref::entry::Entry};
+
+/// A visited page with its URLs interned, see [`Url`] for the fields.
+#[derive(Debug, Clone)]
+struct Page {
+    found_at: NodeId,
+    depth: u32,
+    status: Option<u16>,
+    /// Interned like URLs, as a few content types repeat across all pages.
+    content_type: Option<NodeId>,
+    discovered_at: Option<u64>,
+    noindex: bool,
+    nofollow: bool,
+    skipped: Option<Skip>,
+    /// What is only known once a page is fetched, boxed so that pages
+    /// waiting to be fetched or failing stay small.
+    fetched: Option<Box<Fetched>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Fetched {
+    redirects: Vec<NodeId>,
+    redirect_status: Option<u16>,
+    body_hash: Option<String>,
+    simhash: Option<u64>,
+    metadata: PageMetadata,
+    a11y: Vec<A11yFinding>,
+    security: Vec<SecurityFinding>,
+    security_checked: bool,
+}
+
+/// An edge keyed by its interned source and target.
+#[derive(Debug, Clone, Copy)]
+struct EdgeData {
+    depth: u32,
+    /// One bit per entry of [`NOFOLLOW_RELS`].
+    rel: u8,
+    discovered_at: Option<u64>,
+}
+
+/// The pages and edges of a crawl, stored compactly for crawls of millions of pages.
+///
+/// URLs are interned in a [`UrlTable`] and referred to by id everywhere else. A page
+/// that is only linked or failed to fetch costs under 250 bytes on top of its URL
+/// text, an edge under 80 bytes; `cargo bench --bench memory` measures both.
+///
+/// [`Url`] and [`Edge`] records are built when read. Iterating holds a read lock
+/// on part of the graph, so do not write to it from inside the loop.
+#[derive(Debug, Default)]
+pub struct PageGraph {
+    urls: UrlTable,
+    pages: DashMap<NodeId, Page>,
+    edges: DashMap<(NodeId, NodeId), EdgeData>,
+    in_degree: DashMap<NodeId, u32>,
+}
+
+impl PageGraph {
+    pub fn new() -> Self {
+        PageGraph::default()
+    }
+
+    pub fn urls(&self) -> &UrlTable {
+        &self.urls
+    }
+
+    /// Adds a page, replacing any page with the same URL.
+    pub fn insert_page(&self, url: Url) {
+        let id = self.urls.intern(&url.url);
+        self.pages.insert(id, self.compact(url));
+    }
+
+    /// Adds a page unless there is one with the same URL, returning whether it was added.
+    pub fn insert_new_page(&self, url: Url) -> bool {
+        let id = self.urls.intern(&url.url);
+        match self.pages.entry(id) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(self.compact(url));
+                true
+            }
+        }
+    }
+
+    pub fn remove_page(&self, url: &str) {
+        if let Some(id) = self.urls.get(url) {
+            self.pages.remove(&id);
+        }
+    }
+
+    /// Changes a page in place, if it was visited.
+    pub fn update_page(&self, url: &str, update: impl FnOnce(&mut Url)) {
+        let Some(id) = self.urls.get(url) else {
+            return;
+        };
+        if let Some(mut page) = self.pages.get_mut(&id) {
+            let mut expanded = self.expand(id, &page);
+            update(&mut expanded);
+            *page = self.compact(expanded);
+        }
+    }
+
+    pub fn page(&self, url: &str) -> Option<Url> {
+        let id = self.urls.get(url)?;
+        let page = self.pages.get(&id)?;
+
+        Some(self.expand(id, &page))
+    }
+
+    pub fn contains_page(&self, url: &str) -> bool {
+        self.urls
+            .get(url)
+            .is_some_and(|id| self.pages.contains_key(&id))
+    }
+
+    pub fn is_nofollow(&self, url: &str) -> bool {
+        self.urls
+            .get(url)
+            .and_then(|id| self.pages.get(&id))
+            .is_some_and(|page| page.nofollow)
+    }
+
+    /// Adds an edge unless there is one between the same pages, counting it in the in-degree of its target.
+    pub fn insert_edge(&self, edge: &Edge) -> bool {
+        let source = self.urls.intern(&edge.source);
+        let target = self.urls.intern(&edge.target);
+        match self.edges.entry((source, target)) {
+            Entry::Occupied(_) => return false,
+            Entry::Vacant(entry) => {
+                entry.insert(EdgeData {
+                    depth: edge.depth as u32,
+                    rel: rel_bits(&edge.rel),
+                    discovered_at: edge.discovered_at,
+                });
+            }
+        }
+        *self.in_degree.entry(target).or_insert(0) += 1;
+
+        true
+    }
+
+    pub fn in_degree(&self, url: &str) -> usize {
+        self.urls
+            .get(url)
+            .and_then(|id| self.in_degree.get(&id).map(|count| *count as usize))
+            .unwrap_or(0)
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// The pages, built one at a time.
+    pub fn pages(&self) -> impl Iterator<Item = Url> + '_ {
+        self.pages
+            .iter()
+            .map(|entry| self.expand(*entry.key(), entry.value()))
+    }
+
+    /// The edges, built one at a time.
+    pub fn edges(&self) -> impl Iterator<Item = Edge> + '_ {
+        self.edges.iter().map(|entry| {
+            let (source, target) = *entry.key();
+            let data = entry.value();
+            Edge {
+                source: self.urls.resolve(source).to_string(),
+                target: self.urls.resolve(target).to_string(),
+                depth: data.depth as usize,
+                discovered_at: data.discovered_at,
+                rel: NOFOLLOW_RELS
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| data.rel & (1 << bit) != 0)
+                    .map(|(_, rel)| rel.to_string())
+                    .collect(),
+            }
+        })
+    }
+
+    fn compact(&self, url: Url) -> Page {
+        let fetched = Fetched {
+            redirects: url
+                .redirects
+                .iter()
+                .map(|redirect| self.urls.intern(redirect))
+                .collect(),
+            redirect_status: url.redirect_status,
+            body_hash: url.body_hash,
+            simhash: url.simhash,
+            metadata: url.metadata,
+            a11y: url.a11y,
+            security: url.security,
+            security_checked: url.security_checked,
+        };
+
+        Page {
+            found_at: self.urls.intern(&url.found_at),
+            depth: url.depth as u32,
+            status: url.status,
+            content_type: url
+                .content_type
+                .map(|content_type| self.urls.intern(&content_type)),
+            discovered_at: url.discovered_at,
+            noindex: url.noindex,
+            nofollow: url.nofollow,
+            skipped: url.skipped,
+            fetched: (fetched != Fetched::default()).then(|| Box::new(fetched)),
+        }
+    }
+
+    fn expand(&self, id: NodeId, page: &Page) -> Url {
+        let fetched = page.fetched.as_deref().cloned().unwrap_or_default();
+
+        Url {
+            url: self.urls.resolve(id).to_string(),
+            found_at: self.urls.resolve(page.found_at).to_string(),
+            depth: page.depth as usize,
+            status: page.status,
+            content_type: page
+                .content_type
+                .map(|content_type| self.urls.resolve(content_type).to_string()),
+            discovered_at: page.discovered_at,
+            redirects: fetched
+                .redirects
+                .iter()
+                .map(|redirect| self.urls.resolve(*redirect).to_string())
+                .collect(),
+            redirect_status: fetched.redirect_status,
+            body_hash: fetched.body_hash,
+            simhash: fetched.simhash,
+            metadata: fetched.metadata,
+            noindex: page.noindex,
+            nofollow: page.nofollow,
+            a11y: fetched.a11y,
+            security: fetched.security,
+            security_checked: fetched.security_checked,
+            skipped: page.skipped,
+        }
+    }
+}
+
+fn rel_bits(rel: &[String]) -> u8 {
+    NOFOLLOW_RELS
+        .iter()
+        .enumerate()
+        .filter(|(_, known)| rel.iter().any(|rel| rel == *known))
+        .fold(0, |bits, (bit, _)| bits | 1 << bit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::a11y::A11yRule;
+
+    fn edge(source: &str, target: &str, rel: &[&str]) -> Edge {
+        let mut edge = Edge::new(source.to_string(), target.to_string(), 1);
+        edge.rel = rel.iter().map(|rel| rel.to_string()).collect();
+        edge.discovered_at = Some(1_700_000_000);
+        edge
+    }
+
+    #[test]
+    fn pages_round_trip() {
+        let graph = PageGraph::new();
+        let mut page = Url::new(
+            "https://a.test/a".to_string(),
+            "https://a.test/".to_string(),
+            2,
+        );
+        page.status = Some(200);
+        page.content_type = Some("text/html".to_string());
+        page.discovered_at = Some(1_700_000_000);
+        page.redirects = vec!["https://a.test/b".to_string()];
+        page.redirect_status = Some(301);
+        page.body_hash = Some("ab".repeat(32));
+        page.simhash = Some(42);
+        page.metadata.title = Some("A".to_string());
+        page.nofollow = true;
+        page.a11y = vec![A11yFinding {
+            rule: A11yRule::MissingLang,
+            detail: String::new(),
+        }];
+        page.security_checked = true;
+        graph.insert_page(page.clone());
+
+        let mut linked = Url::new(
+            "https://a.test/big".to_string(),
+            "https://a.test/".to_string(),
+            1,
+        );
+        linked.skipped = Some(Skip::TooLarge);
+        graph.insert_page(linked.clone());
+
+        assert_eq!(graph.page("https://a.test/a"), Some(page));
+        assert_eq!(graph.page("https://a.test/big"), Some(linked));
+        assert_eq!(graph.page("https://a.test/"), None);
+        assert!(!graph.contains_page("https://a.test/b"));
+        assert!(graph.is_nofollow("https://a.test/a"));
+        assert!(!graph.is_nofollow("https://a.test/big"));
+        assert_eq!(graph.page_count(), 2);
+    }
+
+    #[test]
+    fn updates_and_replaces_pages() {
+        let graph = PageGraph::new();
+        let page = Url::new(
+            "https://a.test/".to_string(),
+            "https://a.test/".to_string(),
+            0,
+        );
+        graph.insert_page(page.clone());
+
+        graph.update_page("https://a.test/", |page| page.status = Some(404));
+        graph.update_page("https://a.test/missing", |page| page.status = Some(500));
+        assert_eq!(graph.page("https://a.test/").unwrap().status, Some(404));
+        assert!(!graph.contains_page("https://a.test/missing"));
+
+        graph.insert_page(page);
+        assert_eq!(graph.page("https://a.test/").unwrap().status, None);
+        assert_eq!(graph.pages().count(), 1);
+    }
+
+    #[test]
+    fn new_pages_are_only_inserted_once() {
+        let graph = PageGraph::new();
+        let mut fetched = Url::new(
+            "https://a.test/".to_string(),
+            "https://a.test/".to_string(),
+            0,
+        );
+        fetched.status = Some(200);
+
+        assert!(graph.insert_new_page(fetched.clone()));
+        assert!(!graph.insert_new_page(Url::new(
+            "https://a.test/".to_string(),
+            "https://a.test/other".to_string(),
+            1,
+        )));
+        assert_eq!(graph.page("https://a.test/"), Some(fetched));
+    }
+
+    #[test]
+    fn edges_are_unique_and_keep_their_rels() {
+        let graph = PageGraph::new();
+
+        assert!(graph.insert_edge(&edge("https://a.test/", "https://a.test/b", &[])));
+        assert!(graph.insert_edge(&edge(
+            "https://a.test/c",
+            "https://a.test/b",
+            &["sponsored", "nofollow", "me"]
+        )));
+        assert!(!graph.insert_edge(&edge("https://a.test/", "https://a.test/b", &["ugc"])));
+
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.in_degree("https://a.test/b"), 2);
+        assert_eq!(graph.in_degree("https://a.test/"), 0);
+        assert_eq!(graph.in_degree("https://a.test/unknown"), 0);
+
+        let mut edges = graph.edges().collect::<Vec<_>>();
+        edges.sort_by(|a, b| a.source.cmp(&b.source));
+        assert_eq!(edges[0], edge("https://a.test/", "https://a.test/b", &[]));
+        assert_eq!(
+            edges[1],
+            edge(
+                "https://a.test/c",
+                "https://a.test/b",
+                &["nofollow", "sponsored"]
+            )
+        );
+    }
+}
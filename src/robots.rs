@@ -0,0 +1,152 @@
+use clap::ValueEnum;
+
+/// Link relations asking crawlers not to follow a link.
+pub const NOFOLLOW_RELS: [&str; 3] = ["nofollow", "ugc", "sponsored"];
+
+/// Directives taking a value after a colon, which is not a crawler name then.
+const VALUED_DIRECTIVES: [&str; 4] = [
+    "unavailable_after",
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+];
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RobotsPolicy {
+    /// Follow every link, only record the directives
+    #[default]
+    Ignore,
+    /// Do not follow links from nofollow pages or with a nofollow, ugc or sponsored rel
+    Nofollow,
+    /// Like nofollow, and also leave noindex pages out of exports
+    Strict,
+}
+
+impl RobotsPolicy {
+    pub fn follows_nofollow(&self) -> bool {
+        *self == RobotsPolicy::Ignore
+    }
+
+    pub fn indexes_noindex(&self) -> bool {
+        *self != RobotsPolicy::Strict
+    }
+}
+
+/// Indexing directives from a robots meta tag or an X-Robots-Tag header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Directives {
+    pub noindex: bool,
+    pub nofollow: bool,
+}
+
+impl Directives {
+    /// Parses comma separated directives such as `noindex, nofollow` or `none`.
+    ///
+    /// X-Robots-Tag values may be scoped to a crawler (`googlebot: noindex`),
+    /// those are left out as they are not meant for us.
+    pub fn parse(value: &str) -> Self {
+        let mut directives = Directives::default();
+        let mut scoped = false;
+        for value in value.split(',') {
+            let directive = value.trim().to_ascii_lowercase();
+            if let Some((name, _)) = directive.split_once(':')
+                && !VALUED_DIRECTIVES.contains(&name.trim())
+            {
+                // A crawler name, the directives up to the next one only apply to that crawler.
+                scoped = true;
+            }
+            if scoped {
+                continue;
+            }
+
+            match directive.as_str() {
+                "none" => {
+                    directives.noindex = true;
+                    directives.nofollow = true;
+                }
+                "noindex" => directives.noindex = true,
+                "nofollow" => directives.nofollow = true,
+                _ => {}
+            }
+        }
+
+        directives
+    }
+
+    pub fn merge(self, other: Directives) -> Self {
+        Directives {
+            noindex: self.noindex || other.noindex,
+            nofollow: self.nofollow || other.nofollow,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Args;
+    use clap::Parser;
+
+    fn directives(noindex: bool, nofollow: bool) -> Directives {
+        Directives { noindex, nofollow }
+    }
+
+    #[test]
+    fn parses_meta_directives() {
+        assert_eq!(Directives::parse(""), directives(false, false));
+        assert_eq!(Directives::parse("index, follow"), directives(false, false));
+        assert_eq!(Directives::parse("NOINDEX"), directives(true, false));
+        assert_eq!(
+            Directives::parse(" nofollow ,noarchive"),
+            directives(false, true)
+        );
+        assert_eq!(Directives::parse("none"), directives(true, true));
+    }
+
+    #[test]
+    fn skips_directives_scoped_to_other_crawlers() {
+        assert_eq!(
+            Directives::parse("nofollow, googlebot: noindex"),
+            directives(false, true)
+        );
+        assert_eq!(
+            Directives::parse("otherbot: noindex, nofollow"),
+            directives(false, false)
+        );
+        assert_eq!(
+            Directives::parse("max-snippet: 20, unavailable_after: 2030-01-01, noindex"),
+            directives(true, false)
+        );
+    }
+
+    #[test]
+    fn merges_every_header() {
+        let headers = ["noindex", "unavailable_after: 2030-01-01", "nofollow"]
+            .into_iter()
+            .map(Directives::parse)
+            .fold(Directives::default(), Directives::merge);
+
+        assert_eq!(headers, directives(true, true));
+        assert_eq!(
+            directives(true, false).merge(Directives::default()),
+            directives(true, false)
+        );
+    }
+
+    #[test]
+    fn directives_are_only_recorded_by_default() {
+        let args = Args::parse_from(["crawler", "-u", "https://a.test/", "html"]);
+
+        assert_eq!(args.robots, RobotsPolicy::Ignore);
+        assert_eq!(RobotsPolicy::default(), RobotsPolicy::Ignore);
+    }
+
+    #[test]
+    fn policies() {
+        assert!(RobotsPolicy::Ignore.follows_nofollow());
+        assert!(!RobotsPolicy::Nofollow.follows_nofollow());
+        assert!(RobotsPolicy::Nofollow.indexes_noindex());
+        assert!(!RobotsPolicy::Strict.follows_nofollow());
+        assert!(!RobotsPolicy::Strict.indexes_noindex());
+    }
+}
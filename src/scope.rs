@@ -0,0 +1,179 @@
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScopeMode {
+    /// Follow links anywhere
+    #[default]
+    Any,
+    /// Stay on the scheme, host and port of the start URL
+    Origin,
+    /// Stay on the registrable domain of the start URL, including its subdomains
+    Domain,
+    /// Stay on the origin of the start URL, under a path prefix
+    Path,
+}
+
+/// Decides which URLs the crawl follows, from the start URL and the scope options.
+#[derive(Clone, Debug)]
+pub struct Scope {
+    pub mode: ScopeMode,
+    start: Option<url::Url>,
+    path_prefix: String,
+    allow_hosts: Vec<String>,
+    deny_hosts: Vec<String>,
+}
+
+impl Scope {
+    /// The path prefix defaults to the directory of the start URL.
+    pub fn new(start_url: &str, mode: ScopeMode, path_prefix: Option<&str>) -> Self {
+        let start = url::Url::parse(start_url).ok();
+        let path_prefix = match (path_prefix, &start) {
+            (Some(prefix), _) => prefix.to_string(),
+            (None, Some(start)) => {
+                let path = start.path();
+                path[..path.rfind('/').map_or(0, |index| index + 1)].to_string()
+            }
+            (None, None) => "/".to_string(),
+        };
+
+        Scope {
+            mode,
+            start,
+            path_prefix,
+            allow_hosts: Vec::new(),
+            deny_hosts: Vec::new(),
+        }
+    }
+
+    /// Hosts, with their subdomains, followed whatever the mode.
+    pub fn with_allow_hosts(mut self, hosts: &[String]) -> Self {
+        self.allow_hosts = hosts.iter().map(|host| host.to_lowercase()).collect();
+        self
+    }
+
+    /// Hosts, with their subdomains, never followed.
+    pub fn with_deny_hosts(mut self, hosts: &[String]) -> Self {
+        self.deny_hosts = hosts.iter().map(|host| host.to_lowercase()).collect();
+        self
+    }
+
+    pub fn contains(&self, url: &str) -> bool {
+        let Ok(url) = url::Url::parse(url) else {
+            return false;
+        };
+        let host = url.host_str().unwrap_or_default().to_lowercase();
+        if self.deny_hosts.iter().any(|deny| host_matches(&host, deny)) {
+            return false;
+        }
+        if self
+            .allow_hosts
+            .iter()
+            .any(|allow| host_matches(&host, allow))
+        {
+            return true;
+        }
+        let Some(start) = &self.start else {
+            return self.mode == ScopeMode::Any;
+        };
+
+        match self.mode {
+            ScopeMode::Any => true,
+            ScopeMode::Origin => url.origin() == start.origin(),
+            ScopeMode::Domain => {
+                let start_host = start.host_str().unwrap_or_default().to_lowercase();
+                registrable_domain(&host) == registrable_domain(&start_host)
+            }
+            ScopeMode::Path => {
+                url.origin() == start.origin() && path_matches(url.path(), &self.path_prefix)
+            }
+        }
+    }
+}
+
+/// Whether a path is under a prefix, on a segment boundary: `/docs` covers
+/// `/docs` and `/docs/intro` but not `/docs-old/`.
+fn path_matches(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Whether a host is the given one or one of its subdomains.
+pub(crate) fn host_matches(host: &str, pattern: &str) -> bool {
+    host == pattern
+        || host
+            .strip_suffix(pattern)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// The domain a host belongs to under the public suffix list, like `example.co.uk`
+/// for `www.example.co.uk`. IP addresses and unknown suffixes are their own domain.
+pub(crate) fn registrable_domain(host: &str) -> &str {
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+
+    psl::domain_str(host).unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_follow_the_start_url() {
+        let start = "https://www.a.co.uk/docs/intro";
+        let cases = [
+            ("https://www.a.co.uk/other", [true, true, true, false]),
+            ("http://www.a.co.uk/docs/x", [true, false, true, false]),
+            ("https://shop.a.co.uk/docs/x", [true, false, true, false]),
+            ("https://b.co.uk/", [true, false, false, false]),
+            ("https://www.a.co.uk/docs/x", [true, true, true, true]),
+        ];
+        let modes = [
+            ScopeMode::Any,
+            ScopeMode::Origin,
+            ScopeMode::Domain,
+            ScopeMode::Path,
+        ];
+
+        for (url, expected) in cases {
+            for (mode, expected) in modes.iter().zip(expected) {
+                let scope = Scope::new(start, *mode, None);
+                assert_eq!(scope.contains(url), expected, "{} in {:?}", url, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn path_prefixes_end_on_a_segment() {
+        let scope = Scope::new("https://a.test/", ScopeMode::Path, Some("/docs"));
+
+        assert!(scope.contains("https://a.test/docs"));
+        assert!(scope.contains("https://a.test/docs/intro"));
+        assert!(!scope.contains("https://a.test/docs-old/"));
+        assert!(!scope.contains("https://a.test/doc"));
+
+        let scope = Scope::new("https://a.test/", ScopeMode::Path, Some("/docs/"));
+        assert!(scope.contains("https://a.test/docs/intro"));
+        assert!(!scope.contains("https://a.test/docs"));
+    }
+
+    #[test]
+    fn host_lists_override_the_mode() {
+        let scope = Scope::new("https://a.test/", ScopeMode::Origin, None)
+            .with_allow_hosts(&["cdn.test".to_string()])
+            .with_deny_hosts(&["private.a.test".to_string()]);
+
+        assert!(scope.contains("https://img.cdn.test/x.png"));
+        assert!(!scope.contains("https://notcdn.test/"));
+        assert!(!scope.contains("https://private.a.test/"));
+        assert!(scope.contains("https://a.test/page"));
+    }
+
+    #[test]
+    fn registrable_domains_use_the_public_suffix_list() {
+        assert_eq!(registrable_domain("www.example.co.uk"), "example.co.uk");
+        assert_eq!(registrable_domain("a.b.example.com"), "example.com");
+        assert_eq!(registrable_domain("127.0.0.1"), "127.0.0.1");
+    }
+}
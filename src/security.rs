@@ -0,0 +1,167 @@
+use crate::crawler::resolve_link;
+
+use reqwest::header::HeaderMap;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Security headers every HTTPS page should send, with the issue reported when one is missing.
+const SECURITY_HEADERS: [(&str, SecurityIssue); 4] = [
+    ("strict-transport-security", SecurityIssue::MissingHsts),
+    ("content-security-policy", SecurityIssue::MissingCsp),
+    (
+        "x-content-type-options",
+        SecurityIssue::MissingContentTypeOptions,
+    ),
+    ("referrer-policy", SecurityIssue::MissingReferrerPolicy),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityIssue {
+    /// A subresource loaded over plain HTTP
+    MixedContent,
+    /// A link from the HTTPS page to a plain HTTP URL
+    InsecureLink,
+    /// No Strict-Transport-Security header
+    MissingHsts,
+    /// No Content-Security-Policy header
+    MissingCsp,
+    /// No `X-Content-Type-Options: nosniff` header
+    MissingContentTypeOptions,
+    /// No Referrer-Policy header
+    MissingReferrerPolicy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SecurityFinding {
+    pub issue: SecurityIssue,
+    pub detail: String,
+}
+
+/// Security headers missing from the response to an HTTPS page.
+pub fn check_headers(headers: &HeaderMap) -> Vec<SecurityFinding> {
+    SECURITY_HEADERS
+        .iter()
+        .filter_map(|(name, issue)| {
+            let detail = match headers.get(*name).and_then(|value| value.to_str().ok()) {
+                None => format!("no {} header", name),
+                Some(value)
+                    if *issue == SecurityIssue::MissingContentTypeOptions
+                        && !value.trim().eq_ignore_ascii_case("nosniff") =>
+                {
+                    format!("{} is \"{}\" instead of \"nosniff\"", name, value)
+                }
+                Some(_) => return None,
+            };
+
+            Some(SecurityFinding {
+                issue: *issue,
+                detail,
+            })
+        })
+        .collect()
+}
+
+/// Subresources and links of an HTTPS page that fall back to plain HTTP.
+pub fn check_document(document: &Html, page_url: &str) -> Vec<SecurityFinding> {
+    let subresources = Selector::parse(
+        "img[src], script[src], iframe[src], source[src], video[src], audio[src], embed[src], object[data], link[rel~=stylesheet][href], link[rel~=icon][href]",
+    )
+    .unwrap();
+    let links = Selector::parse("a[href]").unwrap();
+
+    let mut findings = Vec::new();
+    let mut seen = HashSet::new();
+    let mut push = |issue, detail: String| {
+        if seen.insert((issue, detail.clone())) {
+            findings.push(SecurityFinding { issue, detail });
+        }
+    };
+    for element in document.select(&subresources) {
+        let element = element.value();
+        let Some(src) = ["src", "href", "data"]
+            .iter()
+            .find_map(|name| element.attr(name))
+        else {
+            continue;
+        };
+        if let Some(url) = resolve_link(document, page_url, src)
+            && url.starts_with("http:")
+        {
+            push(
+                SecurityIssue::MixedContent,
+                format!("<{}> loads {}", element.name(), url),
+            );
+        }
+    }
+    for link in document.select(&links) {
+        if let Some(url) = link
+            .value()
+            .attr("href")
+            .and_then(|href| resolve_link(document, page_url, href))
+            && url.starts_with("http:")
+        {
+            push(SecurityIssue::InsecureLink, format!("links to {}", url));
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_missing_and_wrong_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "strict-transport-security",
+            "max-age=31536000".parse().unwrap(),
+        );
+        headers.insert("referrer-policy", "no-referrer".parse().unwrap());
+        headers.insert("x-content-type-options", "sniff".parse().unwrap());
+
+        let findings = check_headers(&headers);
+        let issues = findings
+            .iter()
+            .map(|finding| finding.issue)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            vec![
+                SecurityIssue::MissingCsp,
+                SecurityIssue::MissingContentTypeOptions
+            ]
+        );
+        assert_eq!(
+            findings[1].detail,
+            "x-content-type-options is \"sniff\" instead of \"nosniff\""
+        );
+    }
+
+    #[test]
+    fn finds_plain_http_subresources_and_links_once() {
+        let document = Html::parse_document(
+            r#"<img src="http://cdn.test/a.png"><img src="http://cdn.test/a.png">
+            <script src="/app.js"></script>
+            <a href="http://other.test/">Other</a><a href="https://other.test/">Other</a>"#,
+        );
+
+        let findings = check_document(&document, "https://a.test/");
+        assert_eq!(
+            findings,
+            vec![
+                SecurityFinding {
+                    issue: SecurityIssue::MixedContent,
+                    detail: "<img> loads http://cdn.test/a.png".to_string(),
+                },
+                SecurityFinding {
+                    issue: SecurityIssue::InsecureLink,
+                    detail: "links to http://other.test/".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::exporter::{CrawlMetadata, Record};
+use crate::url_data::Skip;
+use crate::{Edge, Url};
+
+use anyhow::{Context, Result};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode, Version};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+/// A single HTTP exchange made while crawling a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fetch {
+    pub url: String,
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub bytes: usize,
+    pub elapsed_ms: u64,
+    pub fetched_at: u64,
+}
+
+/// The raw request and response of a fetch, for sinks archiving them.
+///
+/// A fetch following redirects makes one exchange per hop.
+pub struct Exchange<'a> {
+    pub method: &'a Method,
+    pub url: &'a str,
+    pub remote_addr: Option<SocketAddr>,
+    pub request_headers: &'a HeaderMap,
+    pub version: Version,
+    pub status: StatusCode,
+    pub response_headers: &'a HeaderMap,
+    pub body: &'a [u8],
+    /// Set when the body was not read in full, `body` then holds what was read.
+    pub skipped: Option<Skip>,
+}
+
+/// Receives crawl results incrementally, while the crawl is running.
+pub trait CrawlSink: Send + Sync {
+    /// Called once a page will not change anymore.
+    fn page(&self, _url: &Url) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called the first time a link between two pages is found.
+    fn edge(&self, _edge: &Edge) -> Result<()> {
+        Ok(())
+    }
+
+    fn fetch(&self, _fetch: &Fetch) -> Result<()> {
+        Ok(())
+    }
+
+    fn exchange(&self, _exchange: &Exchange) -> Result<()> {
+        Ok(())
+    }
+
+    fn error(&self, _url: &str, _error: &anyhow::Error) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish(&self, _metadata: &CrawlMetadata) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Streams nodes and edges as JSON Lines, closing with a metadata record.
+pub struct JsonlSink {
+    writer: Mutex<BufWriter<File>>,
+    indexes_noindex: bool,
+}
+
+impl JsonlSink {
+    pub fn create(file_path: &str) -> Result<Self> {
+        let file = File::create(file_path)
+            .context(format!("Failed to create JSON Lines file: {}", file_path))?;
+
+        Ok(JsonlSink {
+            writer: Mutex::new(BufWriter::new(file)),
+            indexes_noindex: true,
+        })
+    }
+
+    /// Whether noindex pages are written, like the exported formats under the robots policy.
+    pub fn with_indexes_noindex(mut self, indexes_noindex: bool) -> Self {
+        self.indexes_noindex = indexes_noindex;
+        self
+    }
+
+    fn write(&self, record: &Record) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        serde_json::to_writer(&mut *writer, record)?;
+        writer.write_all(b"\n")?;
+
+        Ok(())
+    }
+}
+
+impl CrawlSink for JsonlSink {
+    fn page(&self, url: &Url) -> Result<()> {
+        if url.noindex && !self.indexes_noindex {
+            return Ok(());
+        }
+
+        self.write(&Record::Node(url))
+    }
+
+    fn edge(&self, edge: &Edge) -> Result<()> {
+        self.write(&Record::Edge(edge))
+    }
+
+    fn finish(&self, metadata: &CrawlMetadata) -> Result<()> {
+        self.write(&Record::Metadata(metadata))?;
+        self.writer
+            .lock()
+            .unwrap()
+            .flush()
+            .context("Failed to flush JSON Lines file")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noindex_pages_follow_the_robots_policy() {
+        let path = std::env::temp_dir().join(format!("sink-{}.jsonl", uuid::Uuid::new_v4()));
+        let sink = JsonlSink::create(path.to_str().unwrap())
+            .unwrap()
+            .with_indexes_noindex(false);
+        let indexed = Url::new("https://a.test/".to_string(), String::new(), 0);
+        let mut hidden = Url::new("https://a.test/hidden".to_string(), String::new(), 1);
+        hidden.noindex = true;
+
+        sink.page(&indexed).unwrap();
+        sink.page(&hidden).unwrap();
+        sink.finish(&CrawlMetadata::default()).unwrap();
+
+        let lines = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(lines.contains("https://a.test/\""));
+        assert!(!lines.contains("hidden"));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::crawler::USER_AGENT;
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use regex::Regex;
+use std::collections::HashSet;
+use std::io::Read;
+use std::sync::LazyLock;
+use tracing::{info, warn};
+
+/// How many sitemap files are read at most when following sitemap indexes.
+const MAX_SITEMAPS: usize = 100;
+
+static LOC: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<loc>\s*(.*?)\s*</loc>").unwrap());
+static URL_ENTRY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<url>(.*?)</url>").unwrap());
+static PRIORITY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<priority>\s*(.*?)\s*</priority>").unwrap());
+
+/// A URL listed in a sitemap, with its `<priority>` if it has one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    pub url: String,
+    pub priority: Option<f64>,
+}
+
+/// The URLs a sitemap lists, following sitemap indexes to the sitemaps they point at.
+///
+/// Gzipped sitemaps are decompressed. Nested sitemaps that fail to load are
+/// skipped with a warning, only a failure on the first one is an error.
+pub fn fetch_sitemap(url: &str) -> Result<Vec<String>> {
+    Ok(fetch_sitemap_entries(url)?
+        .into_iter()
+        .map(|entry| entry.url)
+        .collect())
+}
+
+/// Like [`fetch_sitemap`], keeping the priority of each URL.
+pub fn fetch_sitemap_entries(url: &str) -> Result<Vec<SitemapEntry>> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .context("Failed to build HTTP client")?;
+
+    let mut urls = Vec::new();
+    let mut queue = vec![url.to_string()];
+    let mut seen = HashSet::new();
+    while let Some(sitemap) = queue.pop() {
+        if seen.len() >= MAX_SITEMAPS {
+            warn!("Stopped reading sitemaps after {}", MAX_SITEMAPS);
+            break;
+        }
+        if !seen.insert(sitemap.clone()) {
+            continue;
+        }
+
+        let body = match read_sitemap(&client, &sitemap) {
+            Ok(body) => body,
+            Err(e) if sitemap != url => {
+                warn!("Skipping sitemap {}: {:?}", sitemap, e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let (entries, sitemaps) = parse_sitemap(&body);
+        urls.extend(entries);
+        queue.extend(sitemaps);
+    }
+    info!("Found {} URLs in sitemap {}", urls.len(), url);
+
+    Ok(urls)
+}
+
+/// The URLs listed in a sitemap, or the sitemaps listed in a sitemap index.
+fn parse_sitemap(body: &str) -> (Vec<SitemapEntry>, Vec<String>) {
+    if body.contains("<sitemapindex") {
+        let sitemaps = LOC
+            .captures_iter(body)
+            .map(|captures| text(&captures[1]))
+            .filter(|url| !url.is_empty())
+            .collect();
+        return (Vec::new(), sitemaps);
+    }
+
+    let entries = URL_ENTRY
+        .captures_iter(body)
+        .filter_map(|captures| {
+            let entry = &captures[1];
+            let url = text(&LOC.captures(entry)?[1]);
+            Some(SitemapEntry {
+                url,
+                priority: PRIORITY
+                    .captures(entry)
+                    .and_then(|captures| text(&captures[1]).parse().ok())
+                    .filter(|priority| (0.0..=1.0).contains(priority)),
+            })
+        })
+        .filter(|entry| !entry.url.is_empty())
+        .collect();
+
+    (entries, Vec::new())
+}
+
+fn read_sitemap(client: &reqwest::blocking::Client, url: &str) -> Result<String> {
+    let response = client
+        .get(url)
+        .send()
+        .context(format!("Failed to send request to {}", url))?
+        .error_for_status()?;
+    let body = response.bytes().context("Failed to read sitemap")?;
+
+    decode_sitemap(&body).context(format!("Failed to decompress sitemap {}", url))
+}
+
+/// The text of a sitemap body, decompressing it if it is gzipped.
+fn decode_sitemap(body: &[u8]) -> Result<String> {
+    if body.starts_with(&[0x1f, 0x8b]) {
+        let mut text = String::new();
+        GzDecoder::new(body).read_to_string(&mut text)?;
+        return Ok(text);
+    }
+
+    Ok(String::from_utf8_lossy(body).into_owned())
+}
+
+/// The text of an element, from a CDATA section or with its entities decoded.
+fn text(value: &str) -> String {
+    let value = value.trim();
+    if let Some(data) = value
+        .strip_prefix("<![CDATA[")
+        .and_then(|value| value.strip_suffix("]]>"))
+    {
+        return data.trim().to_string();
+    }
+
+    unescape(value)
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
+
+    fn entry(url: &str, priority: Option<f64>) -> SitemapEntry {
+        SitemapEntry {
+            url: url.to_string(),
+            priority,
+        }
+    }
+
+    #[test]
+    fn parses_url_sets() {
+        let (entries, sitemaps) = parse_sitemap(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc>https://a.test/</loc><priority>1.0</priority></url>
+              <url>
+                <loc>
+                  https://a.test/search?q=a&amp;page=2
+                </loc>
+                <priority> 0.3 </priority>
+              </url>
+              <url><loc><![CDATA[https://a.test/a&b]]></loc><priority>high</priority></url>
+              <url><loc>https://a.test/nan</loc><priority>NaN</priority></url>
+              <url><loc>https://a.test/big</loc><priority>2</priority></url>
+              <url><loc>https://a.test/none</loc></url>
+              <url><priority>0.5</priority></url>
+              <url><loc> </loc></url>
+            </urlset>"#,
+        );
+
+        assert_eq!(
+            entries,
+            vec![
+                entry("https://a.test/", Some(1.0)),
+                entry("https://a.test/search?q=a&page=2", Some(0.3)),
+                entry("https://a.test/a&b", None),
+                entry("https://a.test/nan", None),
+                entry("https://a.test/big", None),
+                entry("https://a.test/none", None),
+            ]
+        );
+        assert!(sitemaps.is_empty());
+    }
+
+    #[test]
+    fn parses_sitemap_indexes() {
+        let (entries, sitemaps) = parse_sitemap(
+            r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <sitemap><loc>https://a.test/pages.xml</loc></sitemap>
+              <sitemap><loc><![CDATA[ https://a.test/posts.xml.gz ]]></loc></sitemap>
+              <sitemap><loc>https://a.test/s.xml?a=1&amp;b=&lt;2&gt;</loc></sitemap>
+            </sitemapindex>"#,
+        );
+
+        assert!(entries.is_empty());
+        assert_eq!(
+            sitemaps,
+            [
+                "https://a.test/pages.xml",
+                "https://a.test/posts.xml.gz",
+                "https://a.test/s.xml?a=1&b=<2>"
+            ]
+        );
+    }
+
+    #[test]
+    fn unescapes_entities_once() {
+        assert_eq!(
+            unescape("&lt;a&gt; &quot;b&quot; &apos;c&apos; &amp;lt;"),
+            "<a> \"b\" 'c' &lt;"
+        );
+    }
+
+    #[test]
+    fn decompresses_gzipped_sitemaps() {
+        let xml = "<urlset><url><loc>https://a.test/</loc></url></urlset>";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        assert_eq!(decode_sitemap(&gzipped).unwrap(), xml);
+        assert_eq!(decode_sitemap(xml.as_bytes()).unwrap(), xml);
+        assert!(decode_sitemap(&gzipped[..10]).is_err());
+    }
+}
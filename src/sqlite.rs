@@ -0,0 +1,416 @@
+use crate::exporter::CrawlMetadata;
+use crate::metadata::PageMetadata;
+use crate::sink::{CrawlSink, Fetch};
+use crate::url_data::unix_millis;
+use crate::{Edge, Url};
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::sync::Mutex;
+
+const SCHEMA: &str = "
+    PRAGMA journal_mode = WAL;
+    PRAGMA synchronous = NORMAL;
+    PRAGMA foreign_keys = ON;
+
+    CREATE TABLE IF NOT EXISTS urls (
+        id INTEGER PRIMARY KEY,
+        url TEXT NOT NULL UNIQUE
+    );
+
+    CREATE TABLE IF NOT EXISTS pages (
+        url_id INTEGER PRIMARY KEY REFERENCES urls(id),
+        found_at_id INTEGER NOT NULL REFERENCES urls(id),
+        depth INTEGER NOT NULL,
+        status INTEGER,
+        content_type TEXT,
+        title TEXT,
+        discovered_at INTEGER,
+        redirects TEXT,
+        body_hash TEXT,
+        simhash INTEGER,
+        description TEXT,
+        robots TEXT,
+        canonical TEXT,
+        hreflang TEXT,
+        h1 TEXT,
+        h2 TEXT,
+        lang TEXT,
+        word_count INTEGER,
+        noindex INTEGER NOT NULL DEFAULT 0,
+        nofollow INTEGER NOT NULL DEFAULT 0,
+        a11y TEXT,
+        security TEXT,
+        skipped TEXT,
+        redirect_status INTEGER,
+        security_checked INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS pages_status ON pages(status);
+    CREATE INDEX IF NOT EXISTS pages_depth ON pages(depth);
+
+    CREATE TABLE IF NOT EXISTS edges (
+        source_id INTEGER NOT NULL REFERENCES urls(id),
+        target_id INTEGER NOT NULL REFERENCES urls(id),
+        depth INTEGER NOT NULL,
+        discovered_at INTEGER,
+        rel TEXT,
+        PRIMARY KEY (source_id, target_id)
+    );
+    CREATE INDEX IF NOT EXISTS edges_target ON edges(target_id);
+
+    CREATE TABLE IF NOT EXISTS fetches (
+        id INTEGER PRIMARY KEY,
+        url_id INTEGER NOT NULL REFERENCES urls(id),
+        status INTEGER NOT NULL,
+        content_type TEXT,
+        bytes INTEGER NOT NULL,
+        elapsed_ms INTEGER NOT NULL,
+        fetched_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS fetches_url ON fetches(url_id);
+
+    CREATE TABLE IF NOT EXISTS errors (
+        id INTEGER PRIMARY KEY,
+        url_id INTEGER NOT NULL REFERENCES urls(id),
+        message TEXT NOT NULL,
+        occurred_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS errors_url ON errors(url_id);
+
+    CREATE TABLE IF NOT EXISTS crawls (
+        id INTEGER PRIMARY KEY,
+        start_url TEXT NOT NULL,
+        mode TEXT NOT NULL,
+        max_depth INTEGER NOT NULL,
+        started_at INTEGER,
+        finished_at INTEGER,
+        pages INTEGER NOT NULL,
+        edges INTEGER NOT NULL,
+        stopped_by TEXT
+    );
+
+    DROP VIEW IF EXISTS page_urls;
+    CREATE VIEW page_urls AS
+        SELECT u.url, f.url AS found_at, p.depth, p.status, p.content_type, p.title, p.discovered_at,
+            p.body_hash, p.simhash, p.description, p.robots, p.canonical, p.hreflang, p.h1, p.h2,
+            p.lang, p.word_count, p.noindex, p.nofollow, p.redirects, p.a11y, p.security, p.skipped,
+            p.redirect_status, p.security_checked
+        FROM pages p
+        JOIN urls u ON u.id = p.url_id
+        JOIN urls f ON f.id = p.found_at_id;
+
+    DROP VIEW IF EXISTS edge_urls;
+    CREATE VIEW edge_urls AS
+        SELECT s.url AS source, t.url AS target, e.depth, e.discovered_at, e.rel
+        FROM edges e
+        JOIN urls s ON s.id = e.source_id
+        JOIN urls t ON t.id = e.target_id;
+";
+
+/// Stores crawl results in a SQLite database as they are found.
+///
+/// The database doubles as crawl state: [`SqliteSink::load`] returns what a
+/// previous run stored so the crawl can be resumed instead of restarted.
+pub struct SqliteSink {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteSink {
+    pub fn open(file_path: &str) -> Result<Self> {
+        let connection = Connection::open(file_path)
+            .context(format!("Failed to open SQLite database: {}", file_path))?;
+        connection
+            .execute_batch(SCHEMA)
+            .context("Failed to create SQLite schema")?;
+
+        Ok(SqliteSink {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    pub fn load(&self) -> Result<(Vec<Url>, Vec<Edge>)> {
+        let connection = self.connection.lock().unwrap();
+
+        let pages = connection
+            .prepare(
+                "SELECT url, found_at, depth, status, content_type, title, discovered_at, body_hash,
+                    simhash, description, robots, canonical, hreflang, h1, h2, lang, word_count,
+                    noindex, nofollow, redirects, a11y, security, skipped, redirect_status,
+                    security_checked
+                 FROM page_urls",
+            )?
+            .query_map([], |row| {
+                Ok(Url {
+                    url: row.get(0)?,
+                    found_at: row.get(1)?,
+                    depth: row.get(2)?,
+                    status: row.get(3)?,
+                    content_type: row.get(4)?,
+                    discovered_at: row.get(6)?,
+                    redirects: from_json(row.get(19)?),
+                    redirect_status: row.get(23)?,
+                    body_hash: row.get(7)?,
+                    // SQLite integers are signed, fingerprints are stored bit for bit.
+                    simhash: row.get::<_, Option<i64>>(8)?.map(|simhash| simhash as u64),
+                    metadata: PageMetadata {
+                        title: row.get(5)?,
+                        description: row.get(9)?,
+                        robots: row.get(10)?,
+                        canonical: row.get(11)?,
+                        hreflang: from_json(row.get(12)?),
+                        h1: from_json(row.get(13)?),
+                        h2: from_json(row.get(14)?),
+                        lang: row.get(15)?,
+                        word_count: row.get(16)?,
+                    },
+                    noindex: row.get(17)?,
+                    nofollow: row.get(18)?,
+                    a11y: from_json(row.get(20)?),
+                    security: from_json(row.get(21)?),
+                    security_checked: row.get(24)?,
+                    skipped: row
+                        .get::<_, Option<String>>(22)?
+                        .and_then(|skipped| skipped.parse().ok()),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let edges = connection
+            .prepare("SELECT source, target, depth, discovered_at, rel FROM edge_urls")?
+            .query_map([], |row| {
+                Ok(Edge {
+                    source: row.get(0)?,
+                    target: row.get(1)?,
+                    depth: row.get(2)?,
+                    discovered_at: row.get(3)?,
+                    rel: from_json(row.get(4)?),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok((pages, edges))
+    }
+}
+
+fn url_id(connection: &Connection, url: &str) -> Result<i64> {
+    let id = connection
+        .query_row("SELECT id FROM urls WHERE url = ?1", [url], |row| {
+            row.get(0)
+        })
+        .optional()?;
+
+    match id {
+        Some(id) => Ok(id),
+        None => {
+            connection.execute("INSERT INTO urls (url) VALUES (?1)", [url])?;
+            Ok(connection.last_insert_rowid())
+        }
+    }
+}
+
+impl CrawlSink for SqliteSink {
+    fn page(&self, url: &Url) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        let url_id = url_id(&connection, &url.url)?;
+        let found_at_id = url_id_or_self(&connection, &url.found_at, url_id)?;
+        let metadata = &url.metadata;
+
+        connection.execute(
+            "INSERT INTO pages (url_id, found_at_id, depth, status, content_type, title, discovered_at,
+                body_hash, simhash, description, robots, canonical, hreflang, h1, h2, lang, word_count,
+                noindex, nofollow, redirects, a11y, security, skipped, redirect_status,
+                security_checked)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25)
+             ON CONFLICT (url_id) DO UPDATE SET
+                status = excluded.status,
+                content_type = excluded.content_type,
+                title = excluded.title,
+                body_hash = excluded.body_hash,
+                simhash = excluded.simhash,
+                description = excluded.description,
+                robots = excluded.robots,
+                canonical = excluded.canonical,
+                hreflang = excluded.hreflang,
+                h1 = excluded.h1,
+                h2 = excluded.h2,
+                lang = excluded.lang,
+                word_count = excluded.word_count,
+                noindex = excluded.noindex,
+                nofollow = excluded.nofollow,
+                redirects = excluded.redirects,
+                a11y = excluded.a11y,
+                security = excluded.security,
+                skipped = excluded.skipped,
+                redirect_status = excluded.redirect_status,
+                security_checked = excluded.security_checked",
+            params![
+                url_id,
+                found_at_id,
+                url.depth,
+                url.status,
+                url.content_type,
+                metadata.title,
+                url.discovered_at,
+                url.body_hash,
+                url.simhash.map(|simhash| simhash as i64),
+                metadata.description,
+                metadata.robots,
+                metadata.canonical,
+                serde_json::to_string(&metadata.hreflang)?,
+                serde_json::to_string(&metadata.h1)?,
+                serde_json::to_string(&metadata.h2)?,
+                metadata.lang,
+                metadata.word_count,
+                url.noindex,
+                url.nofollow,
+                serde_json::to_string(&url.redirects)?,
+                serde_json::to_string(&url.a11y)?,
+                serde_json::to_string(&url.security)?,
+                url.skipped.map(|skipped| skipped.to_string()),
+                url.redirect_status,
+                url.security_checked
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn edge(&self, edge: &Edge) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        let source_id = url_id(&connection, &edge.source)?;
+        let target_id = url_id(&connection, &edge.target)?;
+
+        connection.execute(
+            "INSERT OR IGNORE INTO edges (source_id, target_id, depth, discovered_at, rel)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                source_id,
+                target_id,
+                edge.depth,
+                edge.discovered_at,
+                serde_json::to_string(&edge.rel)?
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn fetch(&self, fetch: &Fetch) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        let url_id = url_id(&connection, &fetch.url)?;
+
+        connection.execute(
+            "INSERT INTO fetches (url_id, status, content_type, bytes, elapsed_ms, fetched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                url_id,
+                fetch.status,
+                fetch.content_type,
+                fetch.bytes,
+                fetch.elapsed_ms,
+                fetch.fetched_at
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn error(&self, url: &str, error: &anyhow::Error) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        let url_id = url_id(&connection, url)?;
+
+        connection.execute(
+            "INSERT INTO errors (url_id, message, occurred_at) VALUES (?1, ?2, ?3)",
+            params![url_id, format!("{:#}", error), unix_millis()],
+        )?;
+
+        Ok(())
+    }
+
+    fn finish(&self, metadata: &CrawlMetadata) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+
+        connection.execute(
+            "INSERT INTO crawls (start_url, mode, max_depth, started_at, finished_at, pages, edges,
+                stopped_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                metadata.start_url,
+                metadata.mode,
+                metadata.max_depth,
+                metadata.started_at,
+                metadata.finished_at,
+                metadata.pages,
+                metadata.edges,
+                metadata.stopped_by.map(|budget| budget.to_string())
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Lists are stored as JSON arrays; missing or unreadable ones load as empty.
+fn from_json<T: serde::de::DeserializeOwned + Default>(json: Option<String>) -> T {
+    json.and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// The root page has no referrer; it is stored as found at itself.
+fn url_id_or_self(connection: &Connection, url: &str, self_id: i64) -> Result<i64> {
+    match url.is_empty() {
+        true => Ok(self_id),
+        false => url_id(connection, url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Skip;
+
+    #[test]
+    fn pages_and_edges_round_trip() {
+        let sink = SqliteSink::open(":memory:").unwrap();
+        let mut page = Url::new(
+            "https://a.test/a".to_string(),
+            "https://a.test/".to_string(),
+            1,
+        );
+        page.status = Some(200);
+        page.redirects = vec!["https://a.test/b".to_string()];
+        page.redirect_status = Some(301);
+        page.simhash = Some(u64::MAX);
+        page.metadata.title = Some("A".to_string());
+        page.metadata.h1 = vec!["One".to_string(), "Two".to_string()];
+        page.noindex = true;
+        page.security_checked = true;
+        page.skipped = Some(Skip::TooLarge);
+        let mut edge = Edge::new(page.found_at.clone(), page.url.clone(), 1);
+        edge.rel = vec!["nofollow".to_string()];
+
+        sink.page(&Url {
+            status: None,
+            ..page.clone()
+        })
+        .unwrap();
+        sink.page(&page).unwrap();
+        sink.edge(&edge).unwrap();
+        sink.edge(&edge).unwrap();
+
+        let (pages, edges) = sink.load().unwrap();
+        assert_eq!(pages, vec![page]);
+        assert_eq!(edges, vec![edge]);
+    }
+
+    #[test]
+    fn the_start_page_is_found_at_itself() {
+        let sink = SqliteSink::open(":memory:").unwrap();
+        sink.page(&Url::new("https://a.test/".to_string(), String::new(), 0))
+            .unwrap();
+
+        let (pages, _) = sink.load().unwrap();
+        assert_eq!(pages[0].found_at, "https://a.test/");
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// Keeps response bodies on disk, named after the SHA-256 of their content.
+///
+/// Identical bodies fetched from different URLs are stored once; pages refer to
+/// their blob through [`crate::Url::body_hash`].
+pub struct BodyStore {
+    root: PathBuf,
+}
+
+impl BodyStore {
+    pub fn open(dir: &str) -> Result<Self> {
+        std::fs::create_dir_all(dir).context(format!("Failed to create directory: {}", dir))?;
+
+        Ok(BodyStore {
+            root: PathBuf::from(dir),
+        })
+    }
+
+    /// Where the blob with the given hash lives, fanned out by its first two characters.
+    ///
+    /// Fails unless the hash is 64 lowercase hex characters, as made by [`body_hash`].
+    pub fn path(&self, hash: &str) -> Result<PathBuf> {
+        if hash.len() != 64 || !hash.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f')) {
+            return Err(anyhow::anyhow!("Invalid body hash: {:?}", hash));
+        }
+
+        Ok(self.root.join(&hash[..2]).join(&hash[2..]))
+    }
+
+    /// Stores a body unless an identical one is already there, returning its hash.
+    pub fn put(&self, body: &[u8]) -> Result<String> {
+        let hash = body_hash(body);
+        let path = self.path(&hash)?;
+        if path.exists() {
+            return Ok(hash);
+        }
+
+        let dir = self.root.join(&hash[..2]);
+        std::fs::create_dir_all(&dir)
+            .context(format!("Failed to create directory: {}", dir.display()))?;
+        // Write under a unique name first so concurrent writers never expose a partial blob.
+        let partial = dir.join(format!(".{}.{}", &hash[2..], uuid::Uuid::new_v4()));
+        std::fs::write(&partial, body)
+            .context(format!("Failed to write blob: {}", partial.display()))?;
+        std::fs::rename(&partial, &path)
+            .context(format!("Failed to store blob: {}", path.display()))?;
+
+        Ok(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Result<Vec<u8>> {
+        let path = self.path(hash)?;
+        std::fs::read(&path).context(format!("Failed to read blob: {}", path.display()))
+    }
+}
+
+/// Hex-encoded SHA-256 of a body.
+pub fn body_hash(body: &[u8]) -> String {
+    Sha256::digest(body)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> BodyStore {
+        let dir = std::env::temp_dir().join(format!("store-{}", uuid::Uuid::new_v4()));
+        BodyStore::open(dir.to_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn blobs_are_fanned_out_by_hash() {
+        let store = store();
+        let hash = store.put(b"body").unwrap();
+
+        assert_eq!(hash, body_hash(b"body"));
+        assert_eq!(
+            store.path(&hash).unwrap(),
+            store.root.join(&hash[..2]).join(&hash[2..])
+        );
+        assert_eq!(store.get(&hash).unwrap(), b"body");
+        std::fs::remove_dir_all(&store.root).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_hashes() {
+        let store = store();
+        let traversal = format!("../{}", "a".repeat(61));
+
+        for hash in ["", "a", "é", &traversal, &"A".repeat(64), &"a".repeat(65)] {
+            assert!(store.path(hash).is_err(), "{:?}", hash);
+            assert!(store.get(hash).is_err(), "{:?}", hash);
+        }
+        std::fs::remove_dir_all(&store.root).unwrap();
+    }
+}
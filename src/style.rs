@@ -0,0 +1,427 @@
+use crate::scope::{host_matches, registrable_domain};
+
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeColor {
+    None,
+    Host,
+    Domain,
+    Depth,
+    Status,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeSize {
+    None,
+    Depth,
+    InDegree,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeColor {
+    None,
+    LinkType,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    /// Converts a hue in `[0, 360)` with fixed saturation and value to RGB.
+    fn from_hue(hue: f32) -> Self {
+        let (s, v) = (0.65, 0.9);
+        let c = v * s;
+        let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+        let m = v - c;
+        let (r, g, b) = match hue as u32 / 60 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let to_u8 = |channel: f32| ((channel + m) * 255.0).round() as u8;
+
+        Rgb::new(to_u8(r), to_u8(g), to_u8(b))
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim_start_matches('#');
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid color (expected #RRGGBB): {}", s));
+        }
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("Invalid color (expected #RRGGBB): {}", s))
+        };
+
+        Ok(Rgb::new(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+/// A `<host>=#RRGGBB` rule pinning the color of a host and its subdomains.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorRule {
+    pub host: String,
+    pub color: Rgb,
+}
+
+impl FromStr for ColorRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, color) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid color rule (expected HOST=#RRGGBB): {}", s))?;
+
+        Ok(ColorRule {
+            host: host.trim().to_lowercase(),
+            color: color.trim().parse()?,
+        })
+    }
+}
+
+impl ColorRule {
+    fn matches(&self, host: &str) -> bool {
+        host_matches(host, &self.host)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NodeStyle {
+    pub color: Option<Rgb>,
+    pub size: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EdgeStyle {
+    pub color: Option<Rgb>,
+}
+
+impl NodeStyle {
+    /// Writes the style as Gephi `r`/`g`/`b`/`size` attributes.
+    pub fn apply(&self, attributes: &mut Map<String, Value>) {
+        if let Some(color) = self.color {
+            apply_color(color, attributes);
+        }
+        if let Some(size) = self.size {
+            attributes.insert("size".to_string(), json!(size));
+        }
+    }
+}
+
+impl EdgeStyle {
+    /// Writes the style as Gephi `r`/`g`/`b` attributes.
+    pub fn apply(&self, attributes: &mut Map<String, Value>) {
+        if let Some(color) = self.color {
+            apply_color(color, attributes);
+        }
+    }
+}
+
+fn apply_color(color: Rgb, attributes: &mut Map<String, Value>) {
+    attributes.insert("r".to_string(), json!(color.r as f32 / 255.0));
+    attributes.insert("g".to_string(), json!(color.g as f32 / 255.0));
+    attributes.insert("b".to_string(), json!(color.b as f32 / 255.0));
+}
+
+const UNKNOWN_COLOR: Rgb = Rgb::new(0x9e, 0x9e, 0x9e);
+const INTERNAL_EDGE_COLOR: Rgb = Rgb::new(0x90, 0xa4, 0xae);
+const EXTERNAL_EDGE_COLOR: Rgb = Rgb::new(0xff, 0x98, 0x00);
+
+const BASE_SIZE: f32 = 10.0;
+
+/// Styling rules used to color and size nodes and edges sent to Gephi.
+#[derive(Clone, Debug)]
+pub struct Styler {
+    pub node_color: NodeColor,
+    pub node_size: NodeSize,
+    pub edge_color: EdgeColor,
+    pub color_rules: Vec<ColorRule>,
+    pub max_depth: usize,
+}
+
+impl Styler {
+    pub fn node(&self, url: &str, depth: usize, in_degree: usize) -> NodeStyle {
+        let color = match self.node_color {
+            NodeColor::None => None,
+            NodeColor::Host => host_of(url).map(|host| self.host_color(&host, &host)),
+            NodeColor::Domain => {
+                host_of(url).map(|host| self.host_color(&host, registrable_domain(&host)))
+            }
+            NodeColor::Depth => Some(self.depth_color(depth)),
+            // The status is unknown until the page is fetched, see `Styler::status`.
+            NodeColor::Status => Some(UNKNOWN_COLOR),
+        };
+        let size = match self.node_size {
+            NodeSize::None => None,
+            NodeSize::Depth => Some(BASE_SIZE + 5.0 * self.max_depth.saturating_sub(depth) as f32),
+            NodeSize::InDegree => Some(BASE_SIZE + 4.0 * (in_degree as f32).sqrt()),
+        };
+
+        NodeStyle { color, size }
+    }
+
+    /// Returns the style update for a fetched page, if status coloring is enabled.
+    pub fn status(&self, status: u16) -> Option<NodeStyle> {
+        if self.node_color != NodeColor::Status {
+            return None;
+        }
+        let color = match status {
+            200..=299 => Rgb::new(0x4c, 0xaf, 0x50),
+            300..=399 => Rgb::new(0x21, 0x96, 0xf3),
+            400..=499 => Rgb::new(0xff, 0x98, 0x00),
+            500..=599 => Rgb::new(0xf4, 0x43, 0x36),
+            _ => UNKNOWN_COLOR,
+        };
+
+        Some(NodeStyle {
+            color: Some(color),
+            size: None,
+        })
+    }
+
+    pub fn edge(&self, source: &str, target: &str) -> EdgeStyle {
+        let color = match self.edge_color {
+            EdgeColor::None => None,
+            EdgeColor::LinkType => match (host_of(source), host_of(target)) {
+                (Some(source), Some(target)) if source == target => Some(INTERNAL_EDGE_COLOR),
+                _ => Some(EXTERNAL_EDGE_COLOR),
+            },
+        };
+
+        EdgeStyle { color }
+    }
+
+    fn host_color(&self, host: &str, key: &str) -> Rgb {
+        if let Some(rule) = self.color_rules.iter().find(|rule| rule.matches(host)) {
+            return rule.color;
+        }
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+
+        Rgb::from_hue((hasher.finish() % 360) as f32)
+    }
+
+    fn depth_color(&self, depth: usize) -> Rgb {
+        let max_depth = self.max_depth.max(1) as f32;
+        let ratio = (depth as f32 / max_depth).min(1.0);
+
+        Rgb::from_hue(240.0 * (1.0 - ratio))
+    }
+}
+
+fn host_of(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(|host| host.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styler(node_color: NodeColor, node_size: NodeSize, color_rules: &[&str]) -> Styler {
+        Styler {
+            node_color,
+            node_size,
+            edge_color: EdgeColor::LinkType,
+            color_rules: color_rules
+                .iter()
+                .map(|rule| rule.parse().unwrap())
+                .collect(),
+            max_depth: 3,
+        }
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!("#ff8000".parse(), Ok(Rgb::new(0xff, 0x80, 0x00)));
+        assert_eq!("0A0b0C".parse(), Ok(Rgb::new(0x0a, 0x0b, 0x0c)));
+        for invalid in ["", "#fff", "#ff80001", "#gg8000", "#+1+1+1", "#abcé"] {
+            assert!(invalid.parse::<Rgb>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parses_color_rules() {
+        let rule = " Docs.Example.com = #00ff00".parse::<ColorRule>().unwrap();
+        assert_eq!(rule.host, "docs.example.com");
+        assert_eq!(rule.color, Rgb::new(0, 0xff, 0));
+        assert!(rule.matches("docs.example.com"));
+        assert!(rule.matches("v1.docs.example.com"));
+        assert!(!rule.matches("olddocs.example.com"));
+        assert!(!rule.matches("example.com"));
+
+        assert!("example.com".parse::<ColorRule>().is_err());
+        assert!("example.com=#00ff0".parse::<ColorRule>().is_err());
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let styler = styler(
+            NodeColor::Host,
+            NodeSize::None,
+            &["docs.example.com=#ff0000", "example.com=#00ff00"],
+        );
+        let color = |url| styler.node(url, 0, 0).color;
+
+        assert_eq!(
+            color("https://docs.example.com/"),
+            Some(Rgb::new(0xff, 0, 0))
+        );
+        assert_eq!(
+            color("https://www.example.com/"),
+            Some(Rgb::new(0, 0xff, 0))
+        );
+        assert_eq!(color("https://other.test/a"), color("https://OTHER.test/b"));
+        assert_ne!(
+            color("https://other.test/"),
+            color("https://www.other.test/")
+        );
+        assert_eq!(color("not a url"), None);
+    }
+
+    #[test]
+    fn domain_colors_share_the_registrable_domain() {
+        let styler = styler(NodeColor::Domain, NodeSize::None, &[]);
+        let color = |url| styler.node(url, 0, 0).color;
+
+        assert_eq!(
+            color("https://a.example.co.uk/"),
+            color("https://b.example.co.uk/")
+        );
+        assert_ne!(
+            color("https://example.co.uk/"),
+            color("https://other.co.uk/")
+        );
+    }
+
+    #[test]
+    fn sizes_follow_depth_or_in_degree() {
+        let by_depth = styler(NodeColor::None, NodeSize::Depth, &[]);
+        assert_eq!(by_depth.node("https://a.test/", 0, 9).size, Some(25.0));
+        assert_eq!(by_depth.node("https://a.test/", 3, 9).size, Some(10.0));
+        assert_eq!(by_depth.node("https://a.test/", 5, 9).size, Some(10.0));
+
+        let by_in_degree = styler(NodeColor::None, NodeSize::InDegree, &[]);
+        assert_eq!(by_in_degree.node("https://a.test/", 0, 9).size, Some(22.0));
+        assert_eq!(by_in_degree.node("https://a.test/", 3, 0).size, Some(10.0));
+
+        let unsized_node =
+            styler(NodeColor::None, NodeSize::None, &[]).node("https://a.test/", 0, 9);
+        assert_eq!(unsized_node, NodeStyle::default());
+    }
+
+    #[test]
+    fn depth_colors_go_from_blue_to_red() {
+        let styler = styler(NodeColor::Depth, NodeSize::None, &[]);
+
+        assert_eq!(
+            styler.node("https://a.test/", 0, 0).color,
+            Some(Rgb::new(0x50, 0x50, 0xe6))
+        );
+        assert_eq!(
+            styler.node("https://a.test/", 3, 0).color,
+            Some(Rgb::new(0xe6, 0x50, 0x50))
+        );
+        assert_eq!(
+            styler.node("https://a.test/", 9, 0).color,
+            styler.node("https://a.test/", 3, 0).color
+        );
+    }
+
+    #[test]
+    fn status_colors_only_apply_when_enabled() {
+        let styler = styler(NodeColor::Status, NodeSize::InDegree, &[]);
+        let color = |status| styler.status(status).unwrap().color.unwrap();
+
+        assert_eq!(
+            styler.node("https://a.test/", 0, 0).color,
+            Some(UNKNOWN_COLOR)
+        );
+        assert_eq!(color(204), Rgb::new(0x4c, 0xaf, 0x50));
+        assert_eq!(color(301), Rgb::new(0x21, 0x96, 0xf3));
+        assert_eq!(color(404), Rgb::new(0xff, 0x98, 0x00));
+        assert_eq!(color(503), Rgb::new(0xf4, 0x43, 0x36));
+        assert_eq!(color(99), UNKNOWN_COLOR);
+        assert_eq!(styler.status(200).unwrap().size, None);
+        assert_eq!(
+            self::styler(NodeColor::Host, NodeSize::None, &[]).status(200),
+            None
+        );
+    }
+
+    #[test]
+    fn edges_are_colored_by_link_type() {
+        let styler = styler(NodeColor::None, NodeSize::None, &[]);
+
+        assert_eq!(
+            styler.edge("https://a.test/", "http://a.test/b").color,
+            Some(INTERNAL_EDGE_COLOR)
+        );
+        assert_eq!(
+            styler.edge("https://a.test/", "https://b.test/").color,
+            Some(EXTERNAL_EDGE_COLOR)
+        );
+        assert_eq!(
+            Styler {
+                edge_color: EdgeColor::None,
+                ..styler
+            }
+            .edge("https://a.test/", "https://b.test/"),
+            EdgeStyle::default()
+        );
+    }
+
+    #[test]
+    fn styles_are_written_as_gephi_attributes() {
+        let mut attributes = Map::new();
+        NodeStyle {
+            color: Some(Rgb::new(255, 0, 51)),
+            size: Some(12.5),
+        }
+        .apply(&mut attributes);
+        assert_eq!(
+            Value::Object(attributes),
+            json!({"r": 1.0, "g": 0.0, "b": 51.0f32 / 255.0, "size": 12.5})
+        );
+
+        let mut attributes = Map::new();
+        attributes.insert("label".to_string(), json!("A"));
+        NodeStyle::default().apply(&mut attributes);
+        EdgeStyle::default().apply(&mut attributes);
+        assert_eq!(Value::Object(attributes), json!({"label": "A"}));
+
+        let mut attributes = Map::new();
+        EdgeStyle {
+            color: Some(Rgb::new(0, 255, 0)),
+        }
+        .apply(&mut attributes);
+        assert_eq!(
+            Value::Object(attributes),
+            json!({"r": 0.0, "g": 1.0, "b": 0.0})
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use scraper::{ElementRef, Html, Node};
+
+/// Elements whose text is never rendered.
+const HIDDEN_ELEMENTS: [&str; 6] = ["head", "script", "style", "noscript", "template", "svg"];
+
+/// Number of consecutive words hashed together in a fingerprint.
+const SHINGLE_SIZE: usize = 3;
+
+/// The text a reader sees on the page, with whitespace collapsed.
+pub fn visible_text(document: &Html) -> String {
+    let mut words = Vec::new();
+    collect_text(document.root_element(), &mut words);
+
+    words.join(" ")
+}
+
+fn collect_text<'a>(element: ElementRef<'a>, words: &mut Vec<&'a str>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => words.extend(text.split_whitespace()),
+            Node::Element(child_element) if !HIDDEN_ELEMENTS.contains(&child_element.name()) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    collect_text(child, words);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 64-bit SimHash over word shingles; similar texts get fingerprints a few bits apart.
+///
+/// Returns `None` for text without any words.
+pub fn simhash(text: &str) -> Option<u64> {
+    let words = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut votes = [0i64; 64];
+    for shingle in words.windows(SHINGLE_SIZE.min(words.len())) {
+        let hash = fnv1a(shingle.join(" ").as_bytes());
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            match hash >> bit & 1 {
+                1 => *vote += 1,
+                _ => *vote -= 1,
+            }
+        }
+    }
+
+    Some(
+        votes
+            .iter()
+            .enumerate()
+            .filter(|(_, vote)| **vote > 0)
+            .fold(0, |fingerprint, (bit, _)| fingerprint | 1 << bit),
+    )
+}
+
+/// Share of identical bits between two fingerprints, from 0 to 1.
+pub fn similarity(a: u64, b: u64) -> f64 {
+    1.0 - (a ^ b).count_ones() as f64 / 64.0
+}
+
+/// FNV-1a, used over the std hasher so fingerprints stay stable between builds.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_text_skips_hidden_elements() {
+        let document = Html::parse_document(
+            "<html><head><title>Title</title><style>p {}</style></head>\
+             <body><p>Hello\n   <b>world</b></p><script>var x;</script>\
+             <noscript>Enable scripts</noscript></body></html>",
+        );
+
+        assert_eq!(visible_text(&document), "Hello world");
+    }
+
+    #[test]
+    fn simhash_is_stable_and_ignores_case_and_punctuation() {
+        assert_eq!(simhash(""), None);
+        assert_eq!(simhash(" -- "), None);
+        assert!(simhash("one").is_some());
+        assert_eq!(
+            simhash("The quick brown fox jumps over the lazy dog"),
+            simhash("the quick, brown fox... jumps over THE lazy dog!")
+        );
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn similar_texts_are_closer_than_different_ones() {
+        let article = "Ferris the crab crawls the web one link at a time, \
+                       writing every page it finds into a graph of nodes and edges \
+                       that can be exported to many formats for later analysis";
+        let edited = article.replace("many formats", "several formats");
+        let other = "A recipe for bread needs flour, water, salt and yeast, \
+                     kneaded for ten minutes and left to rise overnight in a warm \
+                     kitchen before it is baked in a very hot oven";
+
+        let (article, edited, other) = (
+            simhash(article).unwrap(),
+            simhash(&edited).unwrap(),
+            simhash(other).unwrap(),
+        );
+        assert_eq!(similarity(article, article), 1.0);
+        assert_eq!(similarity(0, u64::MAX), 0.0);
+        assert!(similarity(article, edited) > 0.85);
+        assert!(similarity(article, edited) > similarity(article, other));
+    }
+}
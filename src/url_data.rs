@@ -1,22 +1,98 @@
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+use crate::a11y::A11yFinding;
+use crate::metadata::PageMetadata;
+use crate::security::SecurityFinding;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Debug, Clone, Default, Eq, PartialEq, Hash)]
 pub struct Url {
     pub url: String,
     pub found_at: String,
     pub depth: usize,
+    pub status: Option<u16>,
+    pub content_type: Option<String>,
+    pub discovered_at: Option<u64>,
+    /// URLs the request was redirected to, in order, ending with the one that answered.
+    pub redirects: Vec<String>,
+    /// The status the URL itself answered with, when it redirected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_status: Option<u16>,
+    /// SHA-256 of the response body, shared by pages with identical content.
+    pub body_hash: Option<String>,
+    /// SimHash of the visible text, close for pages with similar text.
+    #[serde(serialize_with = "serialize_fingerprint")]
+    pub simhash: Option<u64>,
+    #[serde(flatten)]
+    pub metadata: PageMetadata,
+    /// Directives from the robots meta tag and the X-Robots-Tag header.
+    pub noindex: bool,
+    pub nofollow: bool,
+    /// Accessibility problems, only checked when asked for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub a11y: Vec<A11yFinding>,
+    /// Mixed content and missing security headers, only checked on HTTPS pages when asked for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub security: Vec<SecurityFinding>,
+    /// Whether the security checks ran on the page.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub security_checked: bool,
+    /// Why the body was not read or parsed, if it was not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<Skip>,
+}
+
+/// Why the body of a page was left alone.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Skip {
+    /// The content type is not one that is parsed
+    ContentType,
+    /// The body is larger than the maximum body size
+    TooLarge,
+}
+
+impl std::fmt::Display for Skip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Skip::ContentType => "content_type",
+            Skip::TooLarge => "too_large",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for Skip {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "content_type" => Ok(Skip::ContentType),
+            "too_large" => Ok(Skip::TooLarge),
+            _ => Err(format!("Unknown skip reason: {}", s)),
+        }
+    }
 }
 
 impl Url {
-    pub fn to_csv_row(&self) -> String {
-        format!(
-            "{},{},{}",
-            Self::escape_url(&self.url),
-            Self::escape_url(&self.found_at),
-            self.depth
-        )
+    pub fn new(url: String, found_at: String, depth: usize) -> Self {
+        Url {
+            url,
+            found_at,
+            depth,
+            discovered_at: Some(unix_millis()),
+            ..Default::default()
+        }
     }
+}
 
-    fn escape_url(url: &str) -> String {
-        url.replace(',', "%2C").replace('\n', "%0A")
+/// Fingerprints are written as hex, as JSON numbers lose precision above 2^53.
+fn serialize_fingerprint<S: serde::Serializer>(
+    fingerprint: &Option<u64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match fingerprint {
+        Some(fingerprint) => serializer.serialize_str(&format!("{:016x}", fingerprint)),
+        None => serializer.serialize_none(),
     }
 }
 
@@ -29,3 +105,54 @@ impl std::fmt::Display for Url {
         )
     }
 }
+
+#[derive(Serialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Edge {
+    pub source: String,
+    pub target: String,
+    pub depth: usize,
+    pub discovered_at: Option<u64>,
+    /// The `nofollow`, `ugc` and `sponsored` relations of the link.
+    pub rel: Vec<String>,
+}
+
+impl Edge {
+    pub fn new(source: String, target: String, depth: usize) -> Self {
+        Edge {
+            source,
+            target,
+            depth,
+            discovered_at: Some(unix_millis()),
+            rel: Vec::new(),
+        }
+    }
+}
+
+/// A link found on a page, before it is crawled.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Link {
+    pub url: String,
+    /// The `nofollow`, `ugc` and `sponsored` relations of the link.
+    pub rel: Vec<String>,
+}
+
+impl Link {
+    pub fn new(url: String) -> Self {
+        Link {
+            url,
+            rel: Vec::new(),
+        }
+    }
+
+    pub fn is_nofollow(&self) -> bool {
+        !self.rel.is_empty()
+    }
+}
+
+/// Milliseconds since the Unix epoch.
+pub(crate) fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
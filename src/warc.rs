@@ -0,0 +1,507 @@
+use crate::sink::{CrawlSink, Exchange};
+use crate::url_data::{Skip, unix_millis};
+
+use anyhow::{Context, Result};
+use flate2::{Compression, write::GzEncoder};
+use reqwest::header::{HeaderMap, TRANSFER_ENCODING};
+use reqwest::{Method, Version};
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Mutex;
+
+struct WarcFile {
+    writer: BufWriter<File>,
+    written: u64,
+    warcinfo_id: String,
+}
+
+/// Archives every fetched request and response pair in WARC 1.1 files.
+///
+/// Records are gzipped one by one, and a new file is started once the current
+/// one reaches the size limit. Every file opens with its own warcinfo record.
+pub struct WarcWriter {
+    prefix: String,
+    max_size: u64,
+    file: Mutex<Option<WarcFile>>,
+    serial: Mutex<usize>,
+}
+
+impl WarcWriter {
+    pub fn create(prefix: &str, max_size: u64) -> Result<Self> {
+        let warc = WarcWriter {
+            prefix: prefix.to_string(),
+            max_size,
+            file: Mutex::new(None),
+            serial: Mutex::new(0),
+        };
+        *warc.file.lock().unwrap() = Some(warc.open_next()?);
+
+        Ok(warc)
+    }
+
+    fn open_next(&self) -> Result<WarcFile> {
+        let mut serial = self.serial.lock().unwrap();
+        let file_name = format!("{}-{:05}.warc.gz", self.prefix, *serial);
+        *serial += 1;
+
+        let file = File::create(&file_name)
+            .context(format!("Failed to create WARC file: {}", file_name))?;
+        let mut warc_file = WarcFile {
+            writer: BufWriter::new(file),
+            written: 0,
+            warcinfo_id: record_id(),
+        };
+
+        let info = format!(
+            "software: ferris-the-crawler/{}\r\nformat: WARC File Format 1.1\r\nconformsTo: http://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/\r\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        let file_name = file_name.rsplit('/').next().unwrap_or(&file_name);
+        let record = Record::new("warcinfo", &warc_file.warcinfo_id)
+            .header("WARC-Filename", file_name)
+            .header("Content-Type", "application/warc-fields")
+            .block(info.into_bytes());
+        warc_file.write(&record)?;
+
+        Ok(warc_file)
+    }
+}
+
+impl WarcFile {
+    fn write(&mut self, record: &Record) -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        record.write(&mut encoder)?;
+        let compressed = encoder.finish()?;
+
+        self.writer.write_all(&compressed)?;
+        self.written += compressed.len() as u64;
+
+        Ok(())
+    }
+}
+
+impl CrawlSink for WarcWriter {
+    fn exchange(&self, exchange: &Exchange) -> Result<()> {
+        let mut file = self.file.lock().unwrap();
+        if let Some(current) = file.as_mut()
+            && current.written >= self.max_size
+        {
+            current.writer.flush()?;
+            *file = Some(self.open_next()?);
+        }
+        let Some(file) = file.as_mut() else {
+            return Ok(());
+        };
+
+        let request_id = record_id();
+        let response_id = record_id();
+        let ip_address = exchange.remote_addr.map(|addr| addr.ip().to_string());
+
+        let mut response = http_head(
+            &format!(
+                "{:?} {} {}",
+                exchange.version,
+                exchange.status.as_str(),
+                exchange.status.canonical_reason().unwrap_or_default()
+            ),
+            exchange.response_headers,
+        );
+        let payload_digest = digest(exchange.body);
+        response.extend_from_slice(exchange.body);
+
+        let mut record = Record::new("response", &response_id)
+            .header("WARC-Target-URI", exchange.url)
+            .header("WARC-Warcinfo-ID", &file.warcinfo_id)
+            .header("Content-Type", "application/http;msgtype=response")
+            .header("WARC-Payload-Digest", &payload_digest);
+        if let Some(ip_address) = &ip_address {
+            record = record.header("WARC-IP-Address", ip_address);
+        }
+        // The HTTP headers still announce the whole body, only part or none of it was read.
+        match exchange.skipped {
+            Some(Skip::TooLarge) => record = record.header("WARC-Truncated", "length"),
+            Some(Skip::ContentType) => record = record.header("WARC-Truncated", "unspecified"),
+            None => {}
+        }
+        file.write(&record.block(response))?;
+
+        let request = http_head(
+            &request_line(exchange.method, exchange.url, exchange.version),
+            exchange.request_headers,
+        );
+        let mut record = Record::new("request", &request_id)
+            .header("WARC-Target-URI", exchange.url)
+            .header("WARC-Warcinfo-ID", &file.warcinfo_id)
+            .header("WARC-Concurrent-To", &response_id)
+            .header("Content-Type", "application/http;msgtype=request");
+        if let Some(ip_address) = &ip_address {
+            record = record.header("WARC-IP-Address", ip_address);
+        }
+        file.write(&record.block(request))?;
+
+        Ok(())
+    }
+
+    fn finish(&self, _metadata: &crate::CrawlMetadata) -> Result<()> {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            file.writer.flush().context("Failed to flush WARC file")?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Record {
+    headers: Vec<(String, String)>,
+    block: Vec<u8>,
+}
+
+impl Record {
+    fn new(kind: &str, id: &str) -> Self {
+        Record {
+            headers: vec![
+                ("WARC-Type".to_string(), kind.to_string()),
+                ("WARC-Record-ID".to_string(), id.to_string()),
+                ("WARC-Date".to_string(), iso8601(unix_millis())),
+            ],
+            block: Vec::new(),
+        }
+    }
+
+    fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn block(mut self, block: Vec<u8>) -> Self {
+        self.block = block;
+        self
+    }
+
+    fn write(&self, writer: &mut impl Write) -> Result<()> {
+        writer.write_all(b"WARC/1.1\r\n")?;
+        for (name, value) in &self.headers {
+            write!(writer, "{}: {}\r\n", name, value)?;
+        }
+        write!(writer, "WARC-Block-Digest: {}\r\n", digest(&self.block))?;
+        write!(writer, "Content-Length: {}\r\n\r\n", self.block.len())?;
+        writer.write_all(&self.block)?;
+        writer.write_all(b"\r\n\r\n")?;
+
+        Ok(())
+    }
+}
+
+/// An HTTP message up to and including the blank line ending its headers.
+fn http_head(start_line: &str, headers: &HeaderMap) -> Vec<u8> {
+    let mut head = format!("{}\r\n", start_line).into_bytes();
+    for (name, value) in headers {
+        // The body is stored decoded, so the original transfer encoding no longer applies to it.
+        if name == TRANSFER_ENCODING {
+            head.extend_from_slice(b"X-Archive-Orig-");
+        }
+        head.extend_from_slice(name.as_str().as_bytes());
+        head.extend_from_slice(b": ");
+        head.extend_from_slice(value.as_bytes());
+        head.extend_from_slice(b"\r\n");
+    }
+    head.extend_from_slice(b"\r\n");
+
+    head
+}
+
+/// The request line and Host header of a request for the URL.
+fn request_line(method: &Method, url: &str, version: Version) -> String {
+    // HTTP/1.x requests are always sent as HTTP/1.1, whatever version the server answered with.
+    let version = match version {
+        Version::HTTP_09 | Version::HTTP_10 => Version::HTTP_11,
+        version => version,
+    };
+    let Ok(parsed) = url::Url::parse(url) else {
+        return format!("{} {} {:?}", method, url, version);
+    };
+
+    let mut target = parsed.path().to_string();
+    if let Some(query) = parsed.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+    let mut host = parsed.host_str().unwrap_or_default().to_string();
+    if let Some(port) = parsed.port() {
+        host.push_str(&format!(":{}", port));
+    }
+
+    format!("{} {} {:?}\r\nhost: {}", method, target, version, host)
+}
+
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", uuid::Uuid::new_v4())
+}
+
+fn digest(data: &[u8]) -> String {
+    format!("sha1:{}", base32(&Sha1::digest(data)))
+}
+
+/// RFC 4648 base32, as used by WARC digests.
+fn base32(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut encoded = String::new();
+    for chunk in data.chunks(5) {
+        let mut buffer = [0u8; 5];
+        buffer[..chunk.len()].copy_from_slice(chunk);
+        let bits = buffer
+            .iter()
+            .fold(0u64, |bits, byte| bits << 8 | *byte as u64);
+
+        let symbols = (chunk.len() * 8).div_ceil(5);
+        for index in 0..8 {
+            match index < symbols {
+                true => encoded.push(ALPHABET[(bits >> (35 - index * 5)) as usize & 31] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Formats milliseconds since the Unix epoch as an ISO 8601 UTC timestamp.
+fn iso8601(millis: u64) -> String {
+    let seconds = millis / 1000;
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::read_request;
+    use crate::{Args, Crawler};
+    use clap::Parser;
+    use flate2::read::MultiGzDecoder;
+    use reqwest::StatusCode;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    /// Serves `/r`, redirecting to `/a`, and the page `/a` on a local port.
+    fn serve_redirect() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let Ok(request) = read_request(&stream) else {
+                    continue;
+                };
+                let mut response = match request.path.as_str() {
+                    "/r" => "HTTP/1.1 301 Moved Permanently\r\nLocation: /a\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                    _ => "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 5\r\nConnection: close\r\n\r\n".to_string(),
+                };
+                if request.path != "/r" && request.method != "HEAD" {
+                    response.push_str("hello");
+                }
+                let _ = (&stream).write_all(response.as_bytes());
+            }
+        });
+
+        base
+    }
+
+    /// The type and first line of the block of every record in a WARC file.
+    fn read_records(file_name: &str) -> Vec<(String, String)> {
+        let mut records = String::new();
+        MultiGzDecoder::new(File::open(file_name).unwrap())
+            .read_to_string(&mut records)
+            .unwrap();
+
+        records
+            .split("WARC/1.1\r\n")
+            .skip(1)
+            .map(|record| {
+                let (headers, block) = record.split_once("\r\n\r\n").unwrap();
+                let record_type = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("WARC-Type: "))
+                    .unwrap();
+                let first_line = block.lines().next().unwrap_or_default();
+                (record_type.to_string(), first_line.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn base32_follows_rfc_4648() {
+        let cases = [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ];
+        for (data, encoded) in cases {
+            assert_eq!(base32(data.as_bytes()), encoded);
+        }
+        assert_eq!(digest(b""), "sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ");
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(951_782_400_000), "2000-02-29T00:00:00.000Z");
+        assert_eq!(iso8601(1_700_000_000_123), "2023-11-14T22:13:20.123Z");
+    }
+
+    #[test]
+    fn records_end_with_their_block() {
+        let mut written = Vec::new();
+        Record::new("resource", "<urn:uuid:1>")
+            .header("Content-Type", "text/plain")
+            .block(b"hello".to_vec())
+            .write(&mut written)
+            .unwrap();
+
+        let written = String::from_utf8(written).unwrap();
+        assert!(
+            written
+                .starts_with("WARC/1.1\r\nWARC-Type: resource\r\nWARC-Record-ID: <urn:uuid:1>\r\n")
+        );
+        assert!(written.contains("\r\nContent-Type: text/plain\r\n"));
+        assert!(written.contains(&format!("\r\nWARC-Block-Digest: {}\r\n", digest(b"hello"))));
+        assert!(written.ends_with("\r\nContent-Length: 5\r\n\r\nhello\r\n\r\n"));
+    }
+
+    #[test]
+    fn heads_keep_the_original_transfer_encoding_aside() {
+        let mut headers = HeaderMap::new();
+        headers.insert(TRANSFER_ENCODING, "chunked".parse().unwrap());
+        headers.insert("content-type", "text/html".parse().unwrap());
+
+        let head = http_head("HTTP/1.1 200 OK", &headers);
+        assert_eq!(
+            String::from_utf8(head).unwrap(),
+            "HTTP/1.1 200 OK\r\nX-Archive-Orig-transfer-encoding: chunked\r\ncontent-type: text/html\r\n\r\n"
+        );
+        assert_eq!(
+            request_line(
+                &Method::GET,
+                "https://a.test:8443/p?q=1#f",
+                Version::HTTP_10
+            ),
+            "GET /p?q=1 HTTP/1.1\r\nhost: a.test:8443"
+        );
+    }
+
+    #[test]
+    fn marks_cut_bodies_as_truncated() {
+        let prefix = std::env::temp_dir().join(format!("warc-{}", uuid::Uuid::new_v4()));
+        let prefix = prefix.to_str().unwrap();
+        let warc = WarcWriter::create(prefix, u64::MAX).unwrap();
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert("content-length", "10".parse().unwrap());
+        let request_headers = HeaderMap::new();
+        let exchange = |body: &'static [u8], skipped| Exchange {
+            method: &Method::GET,
+            url: "https://a.test/",
+            remote_addr: None,
+            request_headers: &request_headers,
+            version: Version::HTTP_11,
+            status: StatusCode::OK,
+            response_headers: &response_headers,
+            body,
+            skipped,
+        };
+
+        warc.exchange(&exchange(b"whole body", None)).unwrap();
+        warc.exchange(&exchange(b"whole", Some(Skip::TooLarge)))
+            .unwrap();
+        warc.finish(&Default::default()).unwrap();
+
+        let file_name = format!("{}-00000.warc.gz", prefix);
+        let mut records = String::new();
+        MultiGzDecoder::new(File::open(&file_name).unwrap())
+            .read_to_string(&mut records)
+            .unwrap();
+        std::fs::remove_file(&file_name).unwrap();
+        let responses = records
+            .split("WARC/1.1\r\n")
+            .filter(|record| record.contains("WARC-Type: response"))
+            .collect::<Vec<_>>();
+        assert_eq!(responses.len(), 2);
+        assert!(!responses[0].contains("WARC-Truncated"));
+        assert!(responses[0].contains(&format!("WARC-Payload-Digest: {}", digest(b"whole body"))));
+        assert!(responses[1].contains("WARC-Truncated: length\r\n"));
+        assert!(responses[1].ends_with("\r\n\r\nwhole\r\n\r\n"));
+    }
+
+    #[test]
+    fn archives_every_redirect_hop_and_head_request() {
+        let base = serve_redirect();
+        let prefix = std::env::temp_dir().join(format!("warc-{}", uuid::Uuid::new_v4()));
+        let prefix = prefix.to_str().unwrap();
+        let warc = Arc::new(WarcWriter::create(prefix, u64::MAX).unwrap());
+        let start = format!("{}/r", base);
+        let args = Args::parse_from([
+            "crawler",
+            "-u",
+            &start,
+            "-d",
+            "1",
+            "-g",
+            "",
+            "--head-first",
+            "html",
+        ]);
+
+        Crawler::new(args).with_sink(warc).crawl().unwrap();
+
+        let file_name = format!("{}-00000.warc.gz", prefix);
+        let records = read_records(&file_name);
+        std::fs::remove_file(&file_name).unwrap();
+        let record = |record_type: &str, line: &str| (record_type.to_string(), line.to_string());
+        assert_eq!(
+            records,
+            vec![
+                record(
+                    "warcinfo",
+                    &format!("software: ferris-the-crawler/{}", env!("CARGO_PKG_VERSION")),
+                ),
+                record("response", "HTTP/1.1 301 Moved Permanently"),
+                record("request", "HEAD /r HTTP/1.1"),
+                record("response", "HTTP/1.1 200 OK"),
+                record("request", "HEAD /a HTTP/1.1"),
+                record("response", "HTTP/1.1 301 Moved Permanently"),
+                record("request", "GET /r HTTP/1.1"),
+                record("response", "HTTP/1.1 200 OK"),
+                record("request", "GET /a HTTP/1.1"),
+            ]
+        );
+    }
+}
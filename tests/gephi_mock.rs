@@ -0,0 +1,129 @@
+use clap::Parser;
+use ferris_the_crawler::{Args, Crawler, EdgeStyle, GephiClient, MockGephi, NodeStyle, Rgb};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Serves a fixed set of HTML pages on a local port and returns its base URL.
+fn serve_site(pages: &'static [(&'static str, &'static str)]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request_line = String::new();
+            let mut reader = BufReader::new(&stream);
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 || line.trim().is_empty() {
+                    break;
+                }
+            }
+
+            let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+            let response = match pages.iter().find(|(page, _)| *page == path) {
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                ),
+                None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+#[test]
+fn client_operations_build_the_mock_graph() {
+    let mock = MockGephi::start().unwrap();
+    let client = GephiClient::new(&mock.url());
+    let style = NodeStyle {
+        color: Some(Rgb::new(255, 0, 0)),
+        size: Some(12.0),
+    };
+
+    runtime().block_on(async {
+        client.add_node("a", "A", 0, &style).await.unwrap();
+        client
+            .add_node("b", "B", 1, &NodeStyle::default())
+            .await
+            .unwrap();
+        client
+            .add_edge("a-b", "a", "b", true, &EdgeStyle::default())
+            .await
+            .unwrap();
+        client
+            .change_node(
+                "b",
+                &NodeStyle {
+                    color: None,
+                    size: Some(20.0),
+                },
+            )
+            .await
+            .unwrap();
+    });
+
+    assert_eq!(mock.operations().len(), 4);
+    let graph = mock.graph();
+    assert!(graph.has_node("a"));
+    assert!(graph.has_edge("a", "b"));
+    assert_eq!(graph.node("a").unwrap()["r"], 1.0);
+    assert_eq!(graph.node("b").unwrap()["label"], "B");
+    assert_eq!(graph.node("b").unwrap()["size"], 20.0);
+}
+
+#[test]
+fn crawl_streams_the_link_graph() {
+    let base = serve_site(&[
+        ("/", r#"<a href="/a">A</a><a href="/b">B</a>"#),
+        ("/a", r#"<a href="/b">B</a>"#),
+        ("/b", "no links"),
+    ]);
+    let mock = MockGephi::start().unwrap();
+    let args = Args::parse_from(["crawler", "-u", &base, "-d", "2", "html"]);
+
+    let crawler = Crawler::new(args).with_gephi_mock(&mock);
+    crawler.crawl().unwrap();
+
+    let graph = mock.graph();
+    let url = |path: &str| format!("{}{}", base, path);
+    assert!(graph.has_node(&url("/")));
+    assert!(graph.has_edge(&url("/"), &url("/a")));
+    assert!(graph.has_edge(&url("/"), &url("/b")));
+    assert!(graph.has_edge(&url("/a"), &url("/b")));
+}
+
+/// Nodes carry their own crawl depth: the source of an edge at depth `d` sits at
+/// `d - 1` and its target at `d`. Nodes used to be streamed one level too deep.
+#[test]
+fn crawl_streams_node_depths() {
+    let base = serve_site(&[
+        ("/", r#"<a href="/a">A</a>"#),
+        ("/a", r#"<a href="/b">B</a>"#),
+        ("/b", "no links"),
+    ]);
+    let mock = MockGephi::start().unwrap();
+    let args = Args::parse_from(["crawler", "-u", &base, "-d", "3", "html"]);
+
+    let crawler = Crawler::new(args).with_gephi_mock(&mock);
+    crawler.crawl().unwrap();
+
+    let graph = mock.graph();
+    let url = |path: &str| format!("{}{}", base, path);
+    assert_eq!(graph.node(&url("/")).unwrap()["depth"], 0);
+    assert_eq!(graph.node(&url("/a")).unwrap()["depth"], 1);
+    assert_eq!(graph.node(&url("/b")).unwrap()["depth"], 2);
+}